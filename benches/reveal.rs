@@ -0,0 +1,36 @@
+//! Times the opening cascade on increasingly large boards. With few mines the
+//! first click floods almost the whole board, so the time per tile should stay
+//! flat as the board grows.
+
+use std::time::{Duration, Instant};
+
+use minesweeper::game::{board::Board, rules::Rules, Dim, Pos};
+use rand::{rngs::StdRng, SeedableRng};
+
+const RUNS: u32 = 5;
+
+fn main() {
+    println!("{:>11} {:>12} {:>12}", "board", "time (ms)", "ns/tile");
+    for side in [100, 300, 1000, 2000] {
+        let mut total = Duration::ZERO;
+        for seed in 0..RUNS {
+            let mut board = Board::custom(Dim::new(side, side), 200, Rules::default());
+            let mut rng = StdRng::seed_from_u64(seed.into());
+            let center = Pos::new(side / 2, side / 2);
+            board.generate_mines(&mut rng, center);
+            board.generate_tiles();
+            board.first_move = false;
+
+            let start = Instant::now();
+            board.open(&mut rng, center);
+            total += start.elapsed();
+        }
+        let mean = total / RUNS;
+        println!(
+            "{:>11} {:>12.3} {:>12.1}",
+            format!("{side}x{side}"),
+            mean.as_secs_f64() * 1000.0,
+            mean.as_secs_f64() * 1e9 / f64::from(side * side),
+        );
+    }
+}
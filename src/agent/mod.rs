@@ -0,0 +1,104 @@
+pub mod random;
+pub mod solver;
+pub mod tournament;
+
+use crate::game::{board::Board, rules::Rules, Dim, Pos};
+
+/// What a player can see of a single tile.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Hidden,
+    Flagged,
+    Revealed(u8),
+    /// A mine that was set off in a practice game, which went on anyway.
+    Exploded,
+    /// Not part of the board at all.
+    Wall,
+}
+
+/// The visible part of a board, which is all an agent gets to look at.
+pub struct View {
+    pub dim: Dim,
+    pub rules: Rules,
+    pub mines: u32,
+    pub first_move: bool,
+    cells: Vec<Cell>,
+}
+
+impl View {
+    pub fn new(board: &Board) -> View {
+        View {
+            dim: board.dim,
+            rules: board.rules,
+            mines: board.mines,
+            first_move: board.first_move,
+            cells: board
+                .tiles()
+                .iter()
+                .map(|tile| {
+                    if tile.is_wall() {
+                        Cell::Wall
+                    } else if tile.is_exploded() {
+                        Cell::Exploded
+                    } else if tile.is_flagged() {
+                        Cell::Flagged
+                    } else if tile.is_mined() {
+                        tile.value().map_or(Cell::Hidden, Cell::Revealed)
+                    } else {
+                        Cell::Hidden
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Hides the flags, for when they can not be trusted to be right.
+    pub fn forget_flags(&mut self) {
+        for cell in &mut self.cells {
+            if *cell == Cell::Flagged {
+                *cell = Cell::Hidden;
+            }
+        }
+    }
+
+    pub fn get(&self, pos: Pos) -> Cell {
+        self.cells[self.dim.index(pos).expect("Position outside of the board")]
+    }
+
+    pub fn neighbors(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.rules
+            .neighbors(self.dim, pos)
+            .filter(|&adj| self.get(adj) != Cell::Wall)
+    }
+
+    /// Every tile of the board, leaving out walls.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.dim
+            .positions()
+            .filter(|&pos| self.get(pos) != Cell::Wall)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Reveals a hidden tile, or chords when used on a revealed number.
+    Reveal(Pos),
+    Flag(Pos),
+}
+
+pub trait Agent {
+    fn name(&self) -> &'static str;
+
+    /// Called before every game with the seed the game is played with.
+    fn reset(&mut self, _seed: u64) {}
+
+    fn act(&mut self, view: &View) -> Action;
+}
+
+/// Every agent the tournament runner knows about.
+pub fn registry() -> Vec<Box<dyn Agent>> {
+    vec![
+        Box::new(solver::SolverAgent::default()),
+        Box::new(random::RandomAgent::default()),
+    ]
+}
@@ -0,0 +1,35 @@
+use rand::{rngs::StdRng, seq::IteratorRandom, SeedableRng};
+
+use super::{Action, Agent, Cell, View};
+
+/// Reveals hidden tiles at random. Only useful as a baseline.
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl Default for RandomAgent {
+    fn default() -> RandomAgent {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn reset(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn act(&mut self, view: &View) -> Action {
+        let pos = view
+            .positions()
+            .filter(|&pos| view.get(pos) == Cell::Hidden)
+            .choose(&mut self.rng)
+            .expect("No hidden tiles left");
+        Action::Reveal(pos)
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::game::{board::Board, Pos, Stage};
+
+use super::{Action, Agent, Cell, View};
+
+/// Tiles that can be proven safe or proven to be mines from the visible board.
+#[derive(Default)]
+pub struct Deductions {
+    pub safe: HashSet<Pos>,
+    pub mines: HashSet<Pos>,
+}
+
+struct Constraint {
+    hidden: Vec<Pos>,
+    mines: i32,
+}
+
+fn constraints(view: &View, known: &Deductions) -> Vec<Constraint> {
+    view.positions()
+        .filter_map(|pos| {
+            let Cell::Revealed(value) = view.get(pos) else {
+                return None;
+            };
+            let mut mines = i32::from(value);
+            let mut hidden = vec![];
+            for adj in view.neighbors(pos) {
+                match view.get(adj) {
+                    Cell::Flagged | Cell::Exploded => mines -= 1,
+                    Cell::Hidden if known.mines.contains(&adj) => mines -= 1,
+                    Cell::Hidden if !known.safe.contains(&adj) => hidden.push(adj),
+                    _ => (),
+                }
+            }
+            (!hidden.is_empty()).then_some(Constraint { hidden, mines })
+        })
+        .collect()
+}
+
+/// Repeatedly applies the single-tile and subset rules until nothing new can
+/// be deduced. Flags are trusted to be correct.
+///
+/// The rules count one mine per tile, so nothing is deduced with multi-mine
+/// rules, where a flag or a hidden tile can stand for several.
+pub fn deduce(view: &View) -> Deductions {
+    let mut known = Deductions::default();
+    if view.rules.multi_mine {
+        return known;
+    }
+
+    loop {
+        let constraints = constraints(view, &known);
+        let mut found = Deductions::default();
+
+        let mut settle = |cells: &[Pos], mines: i32| {
+            if mines == 0 {
+                found.safe.extend(cells);
+            } else if mines == i32::try_from(cells.len()).unwrap() {
+                found.mines.extend(cells);
+            }
+        };
+
+        // Only constraints that share a tile can contain one another.
+        let mut touching: HashMap<Pos, Vec<&Constraint>> = HashMap::new();
+        for c in &constraints {
+            for &pos in &c.hidden {
+                touching.entry(pos).or_default().push(c);
+            }
+        }
+
+        for a in &constraints {
+            settle(&a.hidden, a.mines);
+            for b in &touching[&a.hidden[0]] {
+                if a.hidden.len() >= b.hidden.len()
+                    || !a.hidden.iter().all(|e| b.hidden.contains(e))
+                {
+                    continue;
+                }
+                let rest: Vec<_> = b
+                    .hidden
+                    .iter()
+                    .filter(|e| !a.hidden.contains(e))
+                    .copied()
+                    .collect();
+                settle(&rest, b.mines - a.mines);
+            }
+        }
+
+        if found.safe.is_subset(&known.safe) && found.mines.is_subset(&known.mines) {
+            return known;
+        }
+        known.safe.extend(found.safe);
+        known.mines.extend(found.mines);
+    }
+}
+
+/// Plays `board` by deduction alone, revealing every tile that can be proven
+/// safe, and returns whether that clears it. A board that can be cleared
+/// this way has a single solution, and can be solved without guessing.
+/// Boards with multi-mine rules are never solved, see `deduce`.
+pub fn solve(board: &mut Board) -> bool {
+    while board.check_state() == Stage::Playing {
+        let view = View::new(board);
+        let mut known = deduce(&view);
+        // Once every mine is accounted for, the rest of the board is safe.
+        if known.safe.is_empty() && !view.rules.multi_mine {
+            let hidden: Vec<Pos> = view
+                .positions()
+                .filter(|&e| view.get(e) == Cell::Hidden && !known.mines.contains(&e))
+                .collect();
+            let flagged = view
+                .positions()
+                .filter(|&e| view.get(e) == Cell::Flagged)
+                .count();
+            if view.mines == u32::try_from(flagged + known.mines.len()).unwrap() {
+                known.safe.extend(hidden);
+            }
+        }
+        if known.safe.is_empty() {
+            return false;
+        }
+        for pos in known.safe {
+            board.mine(pos);
+        }
+    }
+    board.check_state() == Stage::Win
+}
+
+/// How a click that hit a mine is judged.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    /// Nothing on the board could be proven safe, so there was no way around
+    /// guessing.
+    ForcedGuess,
+    /// The tile was provably a mine, or there were safe tiles left to find
+    /// before having to guess.
+    Mistake,
+}
+
+/// Judges revealing the mine at `pos` on the board shown by `view`, which is
+/// the board from before the click. Flags are ignored since they may be wrong.
+/// Returns `None` with multi-mine rules, which the solver can't reason about.
+pub fn judge(mut view: View, pos: Pos) -> Option<Verdict> {
+    if view.rules.multi_mine {
+        return None;
+    }
+    if view.first_move {
+        return Some(Verdict::ForcedGuess);
+    }
+    view.forget_flags();
+    let known = deduce(&view);
+    if known.mines.contains(&pos) || !known.safe.is_empty() {
+        Some(Verdict::Mistake)
+    } else {
+        Some(Verdict::ForcedGuess)
+    }
+}
+
+/// Plays every deducible move and falls back to the tile least likely to be
+/// a mine when it gets stuck.
+pub struct SolverAgent {
+    rng: StdRng,
+}
+
+impl Default for SolverAgent {
+    fn default() -> SolverAgent {
+        SolverAgent {
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+impl SolverAgent {
+    fn guess(&mut self, view: &View, known: &Deductions) -> Pos {
+        let hidden: Vec<_> = view
+            .positions()
+            .filter(|pos| view.get(*pos) == Cell::Hidden && !known.mines.contains(pos))
+            .collect();
+        let flagged = view
+            .positions()
+            .filter(|pos| matches!(view.get(*pos), Cell::Flagged | Cell::Exploded))
+            .count();
+        let left =
+            f64::from(view.mines) - f64::from(u32::try_from(flagged + known.mines.len()).unwrap());
+        let density = left / f64::from(u32::try_from(hidden.len()).unwrap());
+
+        let mut risk: Vec<(Option<f64>, Pos)> = hidden.iter().map(|&pos| (None, pos)).collect();
+        for constraint in constraints(view, known) {
+            let p = f64::from(constraint.mines)
+                / f64::from(u32::try_from(constraint.hidden.len()).unwrap());
+            for (r, pos) in &mut risk {
+                if constraint.hidden.contains(pos) {
+                    *r = Some(r.map_or(p, |r| r.max(p)));
+                }
+            }
+        }
+        let risk: Vec<_> = risk
+            .into_iter()
+            .map(|(r, pos)| (r.unwrap_or(density), pos))
+            .collect();
+
+        let lowest = risk.iter().map(|e| e.0).fold(f64::INFINITY, f64::min);
+        let candidates: Vec<_> = risk
+            .into_iter()
+            .filter(|e| (e.0 - lowest).abs() < f64::EPSILON)
+            .map(|e| e.1)
+            .collect();
+        *candidates.choose(&mut self.rng).unwrap()
+    }
+}
+
+impl Agent for SolverAgent {
+    fn name(&self) -> &'static str {
+        "solver"
+    }
+
+    fn reset(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn act(&mut self, view: &View) -> Action {
+        if view.first_move {
+            // The tile closest to the centre, which may be a wall on masked boards.
+            let center = Pos::new(view.dim.rows / 2, view.dim.cols / 2);
+            let pos = view
+                .positions()
+                .min_by_key(|pos| (pos.row - center.row).pow(2) + (pos.col - center.col).pow(2))
+                .unwrap();
+            return Action::Reveal(pos);
+        }
+
+        let known = deduce(view);
+        if let Some(&pos) = known.safe.iter().min() {
+            Action::Reveal(pos)
+        } else if let Some(&pos) = known.mines.iter().min() {
+            Action::Flag(pos)
+        } else {
+            Action::Reveal(self.guess(view, &known))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{layout::Layout, rules::Rules};
+
+    fn board(text: &str, rules: Rules) -> Board {
+        Layout::parse("test", text).unwrap().board(rules)
+    }
+
+    fn multi_mine() -> Rules {
+        Rules {
+            multi_mine: true,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn deduces_safe_tiles_and_mines() {
+        // Revealing the corner shows a 1 with a single hidden neighbour.
+        let mut board = board("3x1\n..*\n", Rules::default());
+        board.mine(Pos::new(0, 0));
+        board.mine(Pos::new(0, 1));
+        let known = deduce(&View::new(&board));
+        assert!(known.safe.is_empty());
+        assert_eq!(known.mines, HashSet::from([Pos::new(0, 2)]));
+    }
+
+    #[test]
+    fn solves_boards_that_need_no_guessing() {
+        let mut board = board("4x3\n...*\n....\n....\n", Rules::default());
+        board.mine(Pos::new(2, 0));
+        assert!(solve(&mut board));
+        assert!(board.check_state() == Stage::Win);
+    }
+
+    #[test]
+    fn gives_up_on_a_coin_flip() {
+        // The two tiles on the right are the same to every number.
+        let mut board = board("3x2\n..*\n...\n", Rules::default());
+        board.mine(Pos::new(0, 0));
+        assert!(!solve(&mut board));
+    }
+
+    #[test]
+    fn judges_mines_hit_in_practice() {
+        let mut mistake = board("3x1\n..*\n", Rules::default());
+        mistake.mine(Pos::new(0, 0));
+        mistake.mine(Pos::new(0, 1));
+        let view = View::new(&mistake);
+        assert_eq!(judge(view, Pos::new(0, 2)), Some(Verdict::Mistake));
+
+        let mut guess = board("3x2\n..*\n...\n", Rules::default());
+        guess.mine(Pos::new(0, 0));
+        let view = View::new(&guess);
+        assert_eq!(judge(view, Pos::new(0, 2)), Some(Verdict::ForcedGuess));
+    }
+
+    #[test]
+    fn stays_out_of_multi_mine_boards() {
+        // Counting a mine per tile would take the 2 in the middle for both
+        // of its neighbours, when both mines are on the left.
+        let mut board = board("3x1\n2..\n", multi_mine());
+        board.mine(Pos::new(0, 1));
+        let view = View::new(&board);
+        let known = deduce(&view);
+        assert!(known.safe.is_empty() && known.mines.is_empty());
+        assert_eq!(judge(view, Pos::new(0, 0)), None);
+        assert!(!solve(&mut board));
+    }
+}
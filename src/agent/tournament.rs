@@ -0,0 +1,201 @@
+use std::{
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::game::{
+    board::{Board, Size},
+    mask::Mask,
+    rules::Rules,
+    Stage,
+};
+
+use super::{solver::deduce, Action, Agent, Cell, View};
+
+/// The averaged results of one agent on one board size.
+pub struct Record {
+    pub agent: String,
+    pub size: Size,
+    pub games: u32,
+    pub wins: u32,
+    pub mean_time: Duration,
+    pub mean_three_bv: f64,
+    pub mean_guesses: f64,
+}
+
+impl Record {
+    pub fn win_rate(&self) -> f64 {
+        f64::from(self.wins) / f64::from(self.games)
+    }
+}
+
+struct Game {
+    won: bool,
+    time: Duration,
+    three_bv: u32,
+    guesses: u32,
+}
+
+/// Plays the same seeded games with every agent so their results can be
+/// compared directly.
+pub struct Tournament {
+    pub games: u32,
+    pub seed: u64,
+    pub sizes: Vec<Size>,
+    pub rules: Rules,
+    /// Plays on this shape instead of the full rectangle of each size.
+    pub mask: Option<Mask>,
+}
+
+impl Tournament {
+    fn play(&self, agent: &mut dyn Agent, size: Size, seed: u64) -> Game {
+        let mut board = match &self.mask {
+            Some(mask) => Board::masked(mask, mask.mines(size), self.rules),
+            None => Board::new(size, self.rules),
+        };
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut guesses = 0;
+        let start = Instant::now();
+        agent.reset(seed);
+
+        // Agents that keep toggling flags would otherwise never finish.
+        let limit = board.dim.cells() * 4;
+        for _ in 0..limit {
+            if board.check_state() != Stage::Playing {
+                break;
+            }
+            let view = View::new(&board);
+            match agent.act(&view) {
+                Action::Reveal(pos) => {
+                    if view.get(pos) == Cell::Hidden
+                        && !view.first_move
+                        && !deduce(&view).safe.contains(&pos)
+                    {
+                        guesses += 1;
+                    }
+                    board.open(&mut rng, pos);
+                }
+                Action::Flag(pos) => board.flag(pos),
+            }
+        }
+
+        Game {
+            won: board.check_state() == Stage::Win,
+            time: start.elapsed(),
+            three_bv: board.three_bv().1,
+            guesses,
+        }
+    }
+
+    pub fn run(&self, agents: &mut [Box<dyn Agent>]) -> Vec<Record> {
+        let mut records = vec![];
+        for agent in agents {
+            for &size in &self.sizes {
+                let results: Vec<_> = (0..self.games)
+                    .map(|i| self.play(agent.as_mut(), size, self.seed.wrapping_add(u64::from(i))))
+                    .collect();
+                let games = f64::from(self.games);
+                records.push(Record {
+                    agent: agent.name().to_string(),
+                    size,
+                    games: self.games,
+                    wins: u32::try_from(results.iter().filter(|g| g.won).count()).unwrap(),
+                    mean_time: results.iter().map(|g| g.time).sum::<Duration>() / self.games,
+                    mean_three_bv: results.iter().map(|g| f64::from(g.three_bv)).sum::<f64>()
+                        / games,
+                    mean_guesses: results.iter().map(|g| f64::from(g.guesses)).sum::<f64>() / games,
+                });
+            }
+        }
+        records
+    }
+}
+
+pub fn table(records: &[Record]) -> String {
+    let mut out = format!(
+        "{:<10} {:<8} {:>6} {:>8} {:>12} {:>8} {:>8}\n",
+        "agent", "size", "games", "win %", "time (ms)", "3bv", "guesses"
+    );
+    for r in records {
+        writeln!(
+            out,
+            "{:<10} {:<8} {:>6} {:>8.1} {:>12.3} {:>8.1} {:>8.2}",
+            r.agent,
+            r.size.to_string(),
+            r.games,
+            r.win_rate() * 100.0,
+            r.mean_time.as_secs_f64() * 1000.0,
+            r.mean_three_bv,
+            r.mean_guesses
+        )
+        .unwrap();
+    }
+    out
+}
+
+pub fn csv(records: &[Record]) -> String {
+    let mut out =
+        String::from("agent,size,games,wins,win_rate,mean_time_ms,mean_3bv_solved,mean_guesses\n");
+    for r in records {
+        writeln!(
+            out,
+            "{},{},{},{},{:.4},{:.3},{:.2},{:.2}",
+            r.agent,
+            r.size.to_string(),
+            r.games,
+            r.wins,
+            r.win_rate(),
+            r.mean_time.as_secs_f64() * 1000.0,
+            r.mean_three_bv,
+            r.mean_guesses
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::registry;
+
+    #[test]
+    fn plays_seeded_games() {
+        // The seeds wrap around instead of overflowing.
+        let tournament = Tournament {
+            games: 10,
+            seed: u64::MAX - 4,
+            sizes: vec![Size::Small],
+            rules: Rules::default(),
+            mask: None,
+        };
+        let records = tournament.run(&mut registry());
+        let wins = |records: &[Record]| -> Vec<_> {
+            records
+                .iter()
+                .map(|r| (r.agent.clone(), r.size.to_string(), r.wins))
+                .collect()
+        };
+        assert_eq!(
+            wins(&records),
+            [
+                ("solver".to_string(), "Small".to_string(), 10),
+                ("random".to_string(), "Small".to_string(), 0),
+            ]
+        );
+        // The same seeds play the same games.
+        assert_eq!(wins(&tournament.run(&mut registry())), wins(&records));
+
+        let csv = csv(&records);
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("agent,size,games,wins,win_rate,mean_time_ms,mean_3bv_solved,mean_guesses")
+        );
+        assert_eq!(lines.count(), 2);
+        assert!(csv.contains("\nsolver,Small,10,10,1.0000,"));
+        assert_eq!(table(&records).lines().count(), 3);
+    }
+}
@@ -0,0 +1,305 @@
+use std::path::Path;
+
+use resource::{resource, Resource};
+use sdl2::{
+    mixer::{self, Channel, Chunk, LoaderRWops, MAX_VOLUME},
+    rwops::RWops,
+    AudioSubsystem, Sdl,
+};
+
+use crate::game::{board::Board, world::World, Stage};
+
+/// Where sounds replacing the built-in ones are looked for, as
+/// `sounds/<name>.wav`.
+pub const SOUNDS_DIR: &str = "sounds";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sound {
+    Reveal,
+    /// A zero was revealed and opened up the tiles around it.
+    Cascade,
+    Flag,
+    Explosion,
+    Win,
+    /// A menu button was pressed.
+    Click,
+}
+
+impl Sound {
+    pub const ALL: [Sound; 6] = [
+        Sound::Reveal,
+        Sound::Cascade,
+        Sound::Flag,
+        Sound::Explosion,
+        Sound::Win,
+        Sound::Click,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sound::Reveal => "reveal",
+            Sound::Cascade => "cascade",
+            Sound::Flag => "flag",
+            Sound::Explosion => "explosion",
+            Sound::Win => "win",
+            Sound::Click => "click",
+        }
+    }
+
+    fn builtin(self) -> Resource<[u8]> {
+        match self {
+            Sound::Reveal => resource!("res/sounds/reveal.wav"),
+            Sound::Cascade => resource!("res/sounds/cascade.wav"),
+            Sound::Flag => resource!("res/sounds/flag.wav"),
+            Sound::Explosion => resource!("res/sounds/explosion.wav"),
+            Sound::Win => resource!("res/sounds/win.wav"),
+            Sound::Click => resource!("res/sounds/click.wav"),
+        }
+    }
+}
+
+/// What can be heard of a board. One is taken before the clicks of a frame
+/// and one after, and the difference decides the sound.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Snapshot {
+    revealed: u32,
+    zeros: u32,
+    flags: u32,
+    exploded: u32,
+    won: bool,
+}
+
+impl Snapshot {
+    pub fn of(board: &Board) -> Snapshot {
+        Snapshot {
+            revealed: board.revealed(),
+            zeros: board.zeros(),
+            flags: board.flags,
+            exploded: board.exploded,
+            won: board.check_state() == Stage::Win,
+        }
+    }
+
+    pub fn of_world(world: &World) -> Snapshot {
+        Snapshot {
+            revealed: world.revealed,
+            zeros: world.zeros,
+            flags: world.flags,
+            exploded: u32::from(world.check_state() == Stage::Lose),
+            won: false,
+        }
+    }
+
+    /// The sound of going from `self` to `after`, if anything happened. Only
+    /// the loudest event is played when a frame had several.
+    pub fn sound(self, after: Snapshot) -> Option<Sound> {
+        if after.exploded > self.exploded {
+            Some(Sound::Explosion)
+        } else if after.won && !self.won {
+            Some(Sound::Win)
+        } else if after.zeros > self.zeros {
+            // Only a zero opens the area around it. A chord can reveal
+            // several numbers at once, which is still a plain reveal.
+            Some(Sound::Cascade)
+        } else if after.revealed > self.revealed {
+            Some(Sound::Reveal)
+        } else if after.flags != self.flags {
+            Some(Sound::Flag)
+        } else {
+            None
+        }
+    }
+}
+
+/// The open audio device and the loaded sounds, in the order of `Sound::ALL`.
+struct Device {
+    _audio: AudioSubsystem,
+    chunks: Vec<Chunk>,
+}
+
+/// Plays sound effects. Without an audio device the game goes on silently,
+/// so it also runs with SDL's dummy audio driver or none at all.
+pub struct Audio {
+    device: Option<Device>,
+}
+
+impl Audio {
+    /// Opens the audio device at `volume` percent, or falls back to silence
+    /// if that fails.
+    pub fn new(sdl: &Sdl, volume: u8) -> Audio {
+        let device = Audio::open(sdl).map_err(|e| eprintln!("Sound is off: {e}"));
+        let audio = Audio {
+            device: device.ok(),
+        };
+        audio.set_volume(volume);
+        audio
+    }
+
+    fn open(sdl: &Sdl) -> Result<Device, String> {
+        let audio = sdl.audio()?;
+        mixer::open_audio(
+            mixer::DEFAULT_FREQUENCY,
+            mixer::DEFAULT_FORMAT,
+            mixer::DEFAULT_CHANNELS,
+            512,
+        )?;
+        let chunks = Sound::ALL
+            .iter()
+            .map(|&e| load(e))
+            .collect::<Result<_, _>>();
+        match chunks {
+            Ok(chunks) => Ok(Device {
+                _audio: audio,
+                chunks,
+            }),
+            Err(e) => {
+                mixer::close_audio();
+                Err(e)
+            }
+        }
+    }
+
+    pub fn set_volume(&self, volume: u8) {
+        if self.device.is_some() {
+            Channel::all().set_volume(MAX_VOLUME * i32::from(volume.min(100)) / 100);
+        }
+    }
+
+    pub fn play(&self, sound: Sound) {
+        let Some(device) = &self.device else {
+            return;
+        };
+        let chunk = &device.chunks[Sound::ALL.iter().position(|&e| e == sound).unwrap()];
+        // Every channel being busy only means this sound is skipped.
+        let _ = Channel::all().play(chunk, 0);
+    }
+
+    /// Plays what changed between two snapshots of a board, if anything.
+    pub fn play_change(&self, before: Snapshot, after: Snapshot) {
+        if let Some(sound) = before.sound(after) {
+            self.play(sound);
+        }
+    }
+}
+
+impl Drop for Audio {
+    fn drop(&mut self) {
+        if let Some(device) = &mut self.device {
+            // The sounds have to go before the device does.
+            device.chunks.clear();
+            mixer::close_audio();
+        }
+    }
+}
+
+/// Loads `sound` from the sounds directory if it is there, or the built-in
+/// one otherwise.
+fn load(sound: Sound) -> Result<Chunk, String> {
+    let path = format!("{SOUNDS_DIR}/{}.wav", sound.name());
+    if Path::new(&path).exists() {
+        match Chunk::from_file(&path) {
+            Ok(chunk) => return Ok(chunk),
+            Err(e) => eprintln!("Skipping sound {path}: {e}"),
+        }
+    }
+    RWops::from_bytes(&sound.builtin())?.load_wav()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{rules::Rules, Pos};
+
+    fn snapshot(revealed: u32, zeros: u32, flags: u32, exploded: u32, won: bool) -> Snapshot {
+        Snapshot {
+            revealed,
+            zeros,
+            flags,
+            exploded,
+            won,
+        }
+    }
+
+    #[test]
+    fn picks_the_sound_of_a_change() {
+        let before = snapshot(10, 1, 2, 0, false);
+        assert_eq!(before.sound(before), None);
+        assert_eq!(
+            before.sound(snapshot(11, 1, 2, 0, false)),
+            Some(Sound::Reveal)
+        );
+        assert_eq!(
+            before.sound(snapshot(20, 3, 2, 0, false)),
+            Some(Sound::Cascade)
+        );
+        // A chord that reveals a few numbers does not open anything.
+        assert_eq!(
+            before.sound(snapshot(13, 1, 2, 0, false)),
+            Some(Sound::Reveal)
+        );
+        assert_eq!(
+            before.sound(snapshot(10, 1, 3, 0, false)),
+            Some(Sound::Flag)
+        );
+        assert_eq!(
+            before.sound(snapshot(10, 1, 1, 0, false)),
+            Some(Sound::Flag)
+        );
+        assert_eq!(
+            before.sound(snapshot(10, 1, 2, 1, false)),
+            Some(Sound::Explosion)
+        );
+        assert_eq!(before.sound(snapshot(11, 1, 2, 0, true)), Some(Sound::Win));
+    }
+
+    #[test]
+    fn plays_the_loudest_of_a_frame() {
+        let before = snapshot(10, 1, 2, 0, false);
+        // A chord that opens tiles and sets off a mine at once.
+        assert_eq!(
+            before.sound(snapshot(15, 1, 2, 1, false)),
+            Some(Sound::Explosion)
+        );
+        // The last click of a win usually reveals something too.
+        assert_eq!(before.sound(snapshot(30, 4, 3, 0, true)), Some(Sound::Win));
+        // A won game only plays the win once.
+        let won = snapshot(30, 4, 2, 0, true);
+        assert_eq!(won.sound(won), None);
+    }
+
+    #[test]
+    fn hears_a_board() {
+        let layout = crate::game::layout::Layout::parse("test", "4x1\n*...\n").unwrap();
+        let mut board = layout.board(Rules::default());
+        let start = Snapshot::of(&board);
+        board.flag(Pos::new(0, 0));
+        let flagged = Snapshot::of(&board);
+        assert_eq!(start.sound(flagged), Some(Sound::Flag));
+        // The zero on the right opens everything but the mine.
+        board.mine(Pos::new(0, 3));
+        assert_eq!(flagged.sound(Snapshot::of(&board)), Some(Sound::Win));
+    }
+
+    #[test]
+    fn only_zeros_cascade() {
+        let layout = crate::game::layout::Layout::parse("test", "4x2\n.*.*\n....\n").unwrap();
+        let mut board = layout.board(Rules::default());
+        let start = Snapshot::of(&board);
+        board.mine(Pos::new(1, 1));
+        let one = Snapshot::of(&board);
+        assert_eq!(start.sound(one), Some(Sound::Reveal));
+        board.flag(Pos::new(0, 1));
+        let flagged = Snapshot::of(&board);
+        // The chord reveals four numbers but no zero.
+        board.mine(Pos::new(1, 1));
+        assert_eq!(board.revealed(), 5);
+        assert_eq!(flagged.sound(Snapshot::of(&board)), Some(Sound::Reveal));
+
+        let layout = crate::game::layout::Layout::parse("test", "5x2\n*....\n....*\n").unwrap();
+        let mut board = layout.board(Rules::default());
+        let start = Snapshot::of(&board);
+        board.mine(Pos::new(0, 2));
+        assert!(board.check_state() == Stage::Playing);
+        assert_eq!(start.sound(Snapshot::of(&board)), Some(Sound::Cascade));
+    }
+}
@@ -0,0 +1,92 @@
+#![warn(clippy::pedantic)]
+
+use std::{env, path::Path};
+
+use minesweeper::{
+    agent::{
+        registry,
+        tournament::{csv, table, Tournament},
+    },
+    game::{
+        board::Size,
+        mask::Mask,
+        rules::{FirstClick, Neighborhood, Rules, Topology},
+    },
+};
+
+const USAGE: &str = "usage: tournament [--games N] [--seed N] \
+                     [--first-click classic|zero|safe3x3|none] [--topology square|hex] [--wrap] \
+                     [--neighborhood standard|cross|knight|radius2] [--mask FILE] [--csv]";
+
+fn main() -> Result<(), String> {
+    let mut tournament = Tournament {
+        games: 100,
+        seed: 0,
+        sizes: Size::ALL.to_vec(),
+        rules: Rules::default(),
+        mask: None,
+    };
+    let mut as_csv = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => {
+                tournament.games = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or(USAGE)?;
+            }
+            "--seed" => {
+                tournament.seed = args.next().and_then(|n| n.parse().ok()).ok_or(USAGE)?;
+            }
+            "--first-click" => {
+                tournament.rules.first_click = args
+                    .next()
+                    .and_then(|id| FirstClick::from_id(&id))
+                    .ok_or(USAGE)?;
+            }
+            "--topology" => {
+                tournament.rules.topology = args
+                    .next()
+                    .and_then(|id| Topology::from_id(&id))
+                    .ok_or(USAGE)?;
+            }
+            "--wrap" => tournament.rules.wrap = true,
+            "--neighborhood" => {
+                tournament.rules.neighborhood = args
+                    .next()
+                    .and_then(|id| Neighborhood::from_id(&id))
+                    .ok_or(USAGE)?;
+            }
+            "--mask" => {
+                let path = args.next().ok_or(USAGE)?;
+                tournament.mask = Some(Mask::load(Path::new(&path))?);
+            }
+            "--csv" => as_csv = true,
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let rules = tournament.rules;
+    if !rules.neighborhood.supports(rules.topology) {
+        return Err(format!(
+            "the {} neighborhood can not be used on a {} board",
+            rules.neighborhood.id(),
+            rules.topology.id()
+        ));
+    }
+
+    let records = tournament.run(&mut registry());
+    print!(
+        "{}",
+        if as_csv {
+            csv(&records)
+        } else {
+            table(&records)
+        }
+    );
+
+    Ok(())
+}
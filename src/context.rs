@@ -7,6 +7,8 @@ use sdl2::{
     EventPump, Sdl, VideoSubsystem,
 };
 
+use crate::audio::Audio;
+
 pub struct Context {
     pub sdl: Sdl,
     pub video_subsys: VideoSubsystem,
@@ -16,10 +18,13 @@ pub struct Context {
     pub tex_creator: TextureCreator<WindowContext>,
     pub event_pump: EventPump,
     pub font_res: Resource<[u8]>,
+    pub audio: Audio,
 }
 
 impl Context {
-    pub fn new() -> Result<Self, String> {
+    /// Sets up SDL with sound effects at `volume` percent. The game still runs
+    /// if there is no audio device.
+    pub fn new(volume: u8) -> Result<Self, String> {
         let sdl = sdl2::init()?;
         let video_subsys = sdl.video()?;
         let image = image::init(InitFlag::PNG)?;
@@ -38,6 +43,7 @@ impl Context {
             .map_err(|e| e.to_string())?;
         let tex_creator = canvas.texture_creator();
         let event_pump = sdl.event_pump()?;
+        let audio = Audio::new(&sdl, volume);
         Ok(Self {
             sdl,
             video_subsys,
@@ -47,6 +53,7 @@ impl Context {
             tex_creator,
             event_pump,
             font_res: resource!("res/font/opensans.ttf"),
+            audio,
         })
     }
 }
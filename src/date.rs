@@ -0,0 +1,118 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A calendar day in UTC, so everyone gets the same daily board at the same
+/// time.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Date {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System clock is before 1970")
+            .as_secs();
+        Date::from_days(i64::try_from(secs / 86_400).unwrap())
+    }
+
+    /// The date `days` days after 1970-01-01, using the proleptic Gregorian
+    /// calendar.
+    pub fn from_days(days: i64) -> Date {
+        // Counts from 0000-03-01 so the leap day is the last day of the year.
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month + 2) / 5 + 1;
+        let month = if month < 10 { month + 3 } else { month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Date {
+            year: i32::try_from(year).unwrap(),
+            month: u32::try_from(month).unwrap(),
+            day: u32::try_from(day).unwrap(),
+        }
+    }
+
+    /// Reads a date written as `YYYY-MM-DD`.
+    pub fn parse(text: &str) -> Option<Date> {
+        let mut parts = text.splitn(3, '-');
+        let date = Date {
+            year: parts.next()?.parse().ok()?,
+            month: parts.next()?.parse().ok()?,
+            day: parts.next()?.parse().ok()?,
+        };
+        ((1..=12).contains(&date.month) && (1..=31).contains(&date.day)).then_some(date)
+    }
+
+    /// A seed that is the same for everyone on this day.
+    pub fn seed(self) -> u64 {
+        let days = u64::from(self.year.unsigned_abs()) * 10_000
+            + u64::from(self.month) * 100
+            + u64::from(self.day);
+        days.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    #[test]
+    fn counts_days_from_1970() {
+        assert_eq!(Date::from_days(0), date(1970, 1, 1));
+        assert_eq!(Date::from_days(-1), date(1969, 12, 31));
+        assert_eq!(Date::from_days(11_016), date(2000, 2, 29));
+        assert_eq!(Date::from_days(19_783), date(2024, 3, 1));
+        assert_eq!(Date::from_days(-25_508), date(1900, 3, 1));
+        assert_eq!(Date::from_days(47_846), date(2100, 12, 31));
+    }
+
+    #[test]
+    fn parses_what_it_writes() {
+        let day = date(2026, 3, 7);
+        assert_eq!(day.to_string(), "2026-03-07");
+        assert_eq!(Date::parse("2026-03-07"), Some(day));
+    }
+
+    #[test]
+    fn rejects_bad_dates() {
+        for text in [
+            "",
+            "2026",
+            "2026-03",
+            "2026-13-01",
+            "2026-00-10",
+            "2026-01-32",
+            "a-b-c",
+        ] {
+            assert_eq!(Date::parse(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn seeds_differ_between_days() {
+        let day = date(2026, 3, 7);
+        assert_eq!(day.seed(), date(2026, 3, 7).seed());
+        assert_ne!(day.seed(), date(2026, 3, 8).seed());
+        assert_ne!(day.seed(), date(2027, 3, 7).seed());
+    }
+}
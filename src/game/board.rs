@@ -0,0 +1,818 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    time::Duration,
+};
+
+use rand::Rng;
+
+use super::{
+    mask::Mask,
+    rules::{FirstClick, Rules},
+    tile::Tile,
+    Dim, Pos, Stage,
+};
+
+#[derive(Clone, Copy)]
+pub enum Size {
+    Small,
+    Normal,
+    Large,
+}
+
+impl ToString for Size {
+    fn to_string(&self) -> String {
+        match *self {
+            Self::Small => "Small",
+            Self::Normal => "Normal",
+            Self::Large => "Large",
+        }
+        .to_string()
+    }
+}
+
+impl Size {
+    pub const ALL: [Size; 3] = [Size::Small, Size::Normal, Size::Large];
+
+    pub fn dim(self) -> Dim {
+        match self {
+            Self::Small => Dim::new(9, 9),
+            Self::Normal => Dim::new(16, 16),
+            Self::Large => Dim::new(18, 30),
+        }
+    }
+
+    pub fn mines(self) -> u32 {
+        match self {
+            Self::Small => 10,
+            Self::Normal => 40,
+            Self::Large => 99,
+        }
+    }
+
+    /// How long a countdown game on this size lasts.
+    pub fn time_limit(self) -> Duration {
+        Duration::from_secs(match self {
+            Self::Small => 60,
+            Self::Normal => 240,
+            Self::Large => 600,
+        })
+    }
+}
+
+/// What an action did to a board: the tiles it changed, each with the value
+/// from before, and the counts from before. Applying it takes the action back.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Change {
+    tiles: Vec<(usize, Tile)>,
+    counts: Counts,
+}
+
+/// Everything about a board besides its tiles that an action can change.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Counts {
+    lost: bool,
+    first_move: bool,
+    flags: u32,
+    exploded: u32,
+    mine_tiles: u32,
+    revealed: u32,
+    zeros: u32,
+    correct_flags: u32,
+}
+
+/// The playing field without any rendering state, so it can be driven by the
+/// SDL game as well as headless agents.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Board {
+    pub dim: Dim,
+    pub rules: Rules,
+    // Row-major, two bytes per tile.
+    tiles: Vec<Tile>,
+    lost: bool,
+    pub first_move: bool,
+    /// Mines never end the game, however many lives there are. Used for
+    /// practice.
+    pub safe_death: bool,
+    /// The total number of mines, several of which can share a tile with
+    /// multi-mine rules.
+    pub mines: u32,
+    pub flags: u32,
+    /// How many mines have been hit, which only goes above one with lives.
+    pub exploded: u32,
+    mine_tiles: u32,
+    // Kept up to date by `reveal` and `flag` so checking the state of the game
+    // never has to look at the whole board.
+    revealed: u32,
+    // How many of the revealed tiles are zeros, which is what makes a click
+    // open an area.
+    zeros: u32,
+    correct_flags: u32,
+    walls: u32,
+    // Set by `record`, and filled in as tiles change until `take_change`.
+    journal: Option<Change>,
+}
+
+impl Board {
+    pub fn new(size: Size, rules: Rules) -> Board {
+        Board::custom(size.dim(), size.mines(), rules)
+    }
+
+    pub fn custom(dim: Dim, mines: u32, rules: Rules) -> Board {
+        Board {
+            dim,
+            rules,
+            tiles: vec![Tile::default(); dim.cells()],
+            lost: false,
+            first_move: true,
+            safe_death: false,
+            mines,
+            flags: mines,
+            exploded: 0,
+            mine_tiles: 0,
+            revealed: 0,
+            zeros: 0,
+            correct_flags: 0,
+            walls: 0,
+            journal: None,
+        }
+    }
+
+    /// A board in the shape of `mask`, with everything outside of it walled off.
+    pub fn masked(mask: &Mask, mines: u32, rules: Rules) -> Board {
+        let mut board = Board::custom(mask.dim, mines, rules);
+        for pos in mask.dim.positions() {
+            if !mask.is_open(pos) {
+                board.tile_mut(pos).set_wall();
+                board.walls += 1;
+            }
+        }
+        board
+    }
+
+    /// A board with its mines already in `tiles`, which is played without
+    /// laying out any more.
+    pub fn fixed(dim: Dim, tiles: Vec<Tile>, rules: Rules) -> Board {
+        let count =
+            |f: fn(&Tile) -> bool| u32::try_from(tiles.iter().filter(|e| f(e)).count()).unwrap();
+        let mines = tiles.iter().map(|e| u32::from(e.mines())).sum();
+        let mut board = Board::custom(dim, mines, rules);
+        board.mine_tiles = count(|e| e.is_mine());
+        board.walls = count(|e| e.is_wall());
+        board.tiles = tiles;
+        board.generate_tiles();
+        board.first_move = false;
+        board
+    }
+
+    pub fn tiles(&self) -> &[Tile] {
+        &self.tiles
+    }
+
+    fn counts(&self) -> Counts {
+        Counts {
+            lost: self.lost,
+            first_move: self.first_move,
+            flags: self.flags,
+            exploded: self.exploded,
+            mine_tiles: self.mine_tiles,
+            revealed: self.revealed,
+            zeros: self.zeros,
+            correct_flags: self.correct_flags,
+        }
+    }
+
+    /// Starts keeping track of what the following actions change, until
+    /// `take_change` is called.
+    pub fn record(&mut self) {
+        self.journal = Some(Change {
+            tiles: vec![],
+            counts: self.counts(),
+        });
+    }
+
+    /// What changed since `record` was called, or `None` if nothing did.
+    pub fn take_change(&mut self) -> Option<Change> {
+        let mut change = self.journal.take()?;
+        // A tile can be touched several times, and only its first value is
+        // the one from before.
+        let mut seen = HashSet::new();
+        change
+            .tiles
+            .retain(|&(i, was)| seen.insert(i) && was != self.tiles[i]);
+        (!change.tiles.is_empty() || change.counts != self.counts()).then_some(change)
+    }
+
+    /// Takes back `change`, and returns the change that does it again.
+    pub fn apply(&mut self, change: Change) -> Change {
+        let tiles = change
+            .tiles
+            .into_iter()
+            .rev()
+            .map(|(i, tile)| (i, std::mem::replace(&mut self.tiles[i], tile)))
+            .collect();
+        let counts = self.counts();
+        let Counts {
+            lost,
+            first_move,
+            flags,
+            exploded,
+            mine_tiles,
+            revealed,
+            zeros,
+            correct_flags,
+        } = change.counts;
+        self.lost = lost;
+        self.first_move = first_move;
+        self.flags = flags;
+        self.exploded = exploded;
+        self.mine_tiles = mine_tiles;
+        self.revealed = revealed;
+        self.zeros = zeros;
+        self.correct_flags = correct_flags;
+        Change { tiles, counts }
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<Tile> {
+        self.dim.index(pos).map(|i| self.tiles[i])
+    }
+
+    fn tile_mut(&mut self, pos: Pos) -> &mut Tile {
+        let index = self.dim.index(pos).expect("Position outside of the board");
+        self.tile_at(index)
+    }
+
+    /// The tile at `index`, noted down first if a change is being recorded.
+    fn tile_at(&mut self, index: usize) -> &mut Tile {
+        if let Some(journal) = &mut self.journal {
+            journal.tiles.push((index, self.tiles[index]));
+        }
+        &mut self.tiles[index]
+    }
+
+    pub fn neighbors(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        self.rules
+            .neighbors(self.dim, pos)
+            .filter(|&adj| !self.get(adj).unwrap().is_wall())
+    }
+
+    /// Whether the first click policy keeps mines off `pos`.
+    fn protected(&self, first: Pos, pos: Pos) -> bool {
+        match self.rules.first_click {
+            FirstClick::Classic | FirstClick::None => false,
+            FirstClick::Zero => pos == first || self.neighbors(first).any(|e| e == pos),
+            FirstClick::Safe3x3 => (-1..=1).any(|row| {
+                (-1..=1).any(|col| {
+                    self.rules
+                        .wrap_pos(self.dim, Pos::new(first.row + row, first.col + col))
+                        == Some(pos)
+                })
+            }),
+        }
+    }
+
+    pub fn generate_mines<R: Rng>(&mut self, rng: &mut R, first: Pos) {
+        let mut pos;
+        let max = self.rules.tile_mines();
+
+        for _ in 0..self.mines {
+            loop {
+                pos = Pos::new(
+                    rng.gen_range(0..self.dim.rows),
+                    rng.gen_range(0..self.dim.cols),
+                );
+                let tile = self.get(pos).unwrap();
+                if !(tile.mines() >= max || tile.is_wall() || self.protected(first, pos)) {
+                    break;
+                }
+            }
+
+            self.tile_mut(pos).add_mine();
+        }
+
+        if self.rules.first_click == FirstClick::Classic {
+            // Every mine on the first tile moves to the first free tile.
+            let moved = self.get(first).unwrap().mines();
+            self.tile_mut(first).set_value(0);
+            for _ in 0..moved {
+                let corner = self
+                    .dim
+                    .positions()
+                    .find(|&e| {
+                        let tile = self.get(e).unwrap();
+                        !(e == first || tile.mines() >= max || tile.is_wall())
+                    })
+                    .unwrap();
+                self.tile_mut(corner).add_mine();
+            }
+        }
+
+        self.mine_tiles = u32::try_from(self.tiles.iter().filter(|e| e.is_mine()).count()).unwrap();
+    }
+
+    pub fn generate_tiles(&mut self) {
+        for i in 0..self.tiles.len() {
+            if self.tiles[i].is_mine() || self.tiles[i].is_wall() {
+                continue;
+            }
+            let mines = self
+                .neighbors(self.dim.pos(i))
+                .map(|adj| self.get(adj).unwrap().mines())
+                .sum();
+            self.tile_at(i).set_value(mines);
+        }
+    }
+
+    /// Lays out the mines around the first click and reveals it.
+    pub fn open<R: Rng>(&mut self, rng: &mut R, pos: Pos) {
+        if self.first_move {
+            self.generate_mines(rng, pos);
+            self.generate_tiles();
+            self.first_move = false;
+        }
+        self.mine(pos);
+    }
+
+    /// Reveals a tile and, if it is a zero, the whole opening around it. Tiles
+    /// are revealed as they are queued so every one is only visited once.
+    fn reveal(&mut self, pos: Pos) {
+        let tile = self.tile_mut(pos);
+        if tile.is_flagged() || tile.is_mined() || tile.is_wall() {
+            return;
+        }
+        if tile.mine() {
+            tile.explode();
+            // The mine is known now, so it no longer needs a flag.
+            let mines = u32::from(tile.mines());
+            self.flags = self.flags.saturating_sub(mines);
+            self.exploded += 1;
+            self.lost = !self.safe_death && self.exploded >= u32::from(self.rules.lives);
+            return;
+        }
+
+        let mut queue = VecDeque::new();
+        if tile.value() == Some(0) {
+            queue.push_back(pos);
+            self.zeros += 1;
+        }
+        self.revealed += 1;
+        while let Some(pos) = queue.pop_front() {
+            for adj in self.rules.neighbors(self.dim, pos) {
+                let tile = self.tile_mut(adj);
+                if tile.is_flagged() || tile.is_mined() || tile.is_wall() {
+                    continue;
+                }
+                tile.mine();
+                if tile.value() == Some(0) {
+                    queue.push_back(adj);
+                    self.zeros += 1;
+                }
+                self.revealed += 1;
+            }
+        }
+    }
+
+    pub fn mine(&mut self, pos: Pos) {
+        let tile = self.get(pos).unwrap();
+        // A mine that already went off has no number to chord on.
+        if tile.is_flagged() || tile.is_exploded() {
+        } else if tile.is_mined() {
+            // Exploded mines count towards a chord just like flags do.
+            let (known, unknown): (Vec<Pos>, Vec<Pos>) = self
+                .neighbors(pos)
+                .filter(|&e| {
+                    let tile = self.get(e).unwrap();
+                    !tile.is_mined() || tile.is_exploded()
+                })
+                .partition(|&e| {
+                    let tile = self.get(e).unwrap();
+                    tile.is_flagged() || tile.is_exploded()
+                });
+            let known: u8 = known
+                .iter()
+                .map(|&e| {
+                    let tile = self.get(e).unwrap();
+                    if tile.is_exploded() {
+                        tile.mines()
+                    } else {
+                        tile.flags()
+                    }
+                })
+                .sum();
+            if known == tile.value().unwrap() {
+                for t in unknown {
+                    self.reveal(t);
+                }
+            }
+        } else {
+            self.reveal(pos);
+        }
+    }
+
+    /// Adds a flag to a tile, or clears it once it can not take any more.
+    /// Every flag counts against the total number of mines.
+    pub fn flag(&mut self, pos: Pos) {
+        let tile = self.get(pos).unwrap();
+        if tile.is_mined() || tile.is_wall() {
+            return;
+        }
+        let old = tile.flags();
+        let new = if old < self.rules.tile_mines() && self.flags > 0 {
+            old + 1
+        } else {
+            0
+        };
+        let correct = |flags: u8| flags > 0 && flags == tile.mines();
+        self.correct_flags = self.correct_flags + u32::from(correct(new)) - u32::from(correct(old));
+        self.flags = self.flags + u32::from(old) - u32::from(new);
+        self.tile_mut(pos).set_flags(new);
+    }
+
+    /// How many tiles have been revealed.
+    pub fn revealed(&self) -> u32 {
+        self.revealed
+    }
+
+    /// How many of the revealed tiles are zeros.
+    pub fn zeros(&self) -> u32 {
+        self.zeros
+    }
+
+    pub fn check_state(&self) -> Stage {
+        if self.lost {
+            Stage::Lose
+        } else if self.revealed == self.cells() - self.mine_tiles {
+            Stage::Win
+        } else {
+            Stage::Playing
+        }
+    }
+
+    /// How many more mines can be hit before the game is lost.
+    pub fn lives_left(&self) -> u32 {
+        u32::from(self.rules.lives) - self.exploded.min(u32::from(self.rules.lives))
+    }
+
+    /// The number of tiles that can be played on.
+    pub fn cells(&self) -> u32 {
+        u32::try_from(self.tiles.len()).unwrap() - self.walls
+    }
+
+    /// How much of the board has been correctly cleared or flagged.
+    pub fn percentage(&self) -> u32 {
+        let done = u64::from(self.revealed + self.correct_flags);
+        u32::try_from(done * 100 / u64::from(self.cells())).unwrap()
+    }
+
+    /// Returns the 3BV of the board (the minimum number of clicks needed to
+    /// clear it) and how much of it has already been cleared.
+    pub fn three_bv(&self) -> (u32, u32) {
+        let mut seen = vec![false; self.tiles.len()];
+        let (mut total, mut solved) = (0, 0);
+
+        for i in 0..self.tiles.len() {
+            if self.tiles[i].value() != Some(0) || self.tiles[i].is_wall() || seen[i] {
+                continue;
+            }
+            // Every opening counts once, together with the numbers that border it.
+            let mut opened = false;
+            let mut stack = vec![self.dim.pos(i)];
+            seen[i] = true;
+            while let Some(pos) = stack.pop() {
+                let tile = self.get(pos).unwrap();
+                if tile.value() != Some(0) {
+                    continue;
+                }
+                opened |= tile.is_mined();
+                for adj in self.neighbors(pos) {
+                    let j = self.dim.index(adj).unwrap();
+                    if !seen[j] {
+                        seen[j] = true;
+                        stack.push(adj);
+                    }
+                }
+            }
+            total += 1;
+            solved += u32::from(opened);
+        }
+
+        for (tile, seen) in self.tiles.iter().zip(seen) {
+            if !(tile.is_mine() || tile.is_wall() || seen) {
+                total += 1;
+                solved += u32::from(tile.is_mined());
+            }
+        }
+
+        (total, solved)
+    }
+
+    /// The number of openings: groups of touching zeros, each cleared by a
+    /// single click.
+    pub fn openings(&self) -> u32 {
+        self.groups(|_, tile| !tile.is_wall() && tile.value() == Some(0))
+    }
+
+    /// The number of islands: groups of touching numbers that do not border
+    /// an opening, so each of them has to be cleared tile by tile.
+    pub fn islands(&self) -> u32 {
+        self.groups(|pos, tile| {
+            !tile.is_wall()
+                && tile.value().is_some_and(|e| e > 0)
+                && !self
+                    .neighbors(pos)
+                    .any(|adj| self.get(adj).unwrap().value() == Some(0))
+        })
+    }
+
+    /// Counts the groups of touching tiles that `member` picks out.
+    fn groups(&self, member: impl Fn(Pos, Tile) -> bool) -> u32 {
+        let mut seen = vec![false; self.tiles.len()];
+        let mut groups = 0;
+        for pos in self.dim.positions() {
+            let i = self.dim.index(pos).unwrap();
+            if seen[i] || !member(pos, self.tiles[i]) {
+                continue;
+            }
+            groups += 1;
+            seen[i] = true;
+            let mut stack = vec![pos];
+            while let Some(pos) = stack.pop() {
+                for adj in self.neighbors(pos) {
+                    let j = self.dim.index(adj).unwrap();
+                    if !seen[j] && member(adj, self.tiles[j]) {
+                        seen[j] = true;
+                        stack.push(adj);
+                    }
+                }
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    /// A board from rows of `.` and `*`.
+    fn board(rows: &[&str], rules: Rules) -> Board {
+        let dim = Dim::new(
+            i32::try_from(rows.len()).unwrap(),
+            i32::try_from(rows[0].len()).unwrap(),
+        );
+        let tiles = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|c| {
+                let mut tile = Tile::default();
+                if c == '*' {
+                    tile.add_mine();
+                }
+                tile
+            })
+            .collect();
+        Board::fixed(dim, tiles, rules)
+    }
+
+    fn rules(first_click: FirstClick) -> Rules {
+        Rules {
+            first_click,
+            ..Rules::default()
+        }
+    }
+
+    /// A normal board opened at `first` with the mines of `seed`.
+    fn opened(rules: Rules, seed: u64, first: Pos) -> Board {
+        let mut board = Board::new(Size::Normal, rules);
+        board.open(&mut StdRng::seed_from_u64(seed), first);
+        board
+    }
+
+    #[test]
+    fn the_first_click_is_never_a_mine() {
+        for first_click in [FirstClick::Classic, FirstClick::Zero, FirstClick::Safe3x3] {
+            for seed in 0..50 {
+                let board = opened(rules(first_click), seed, Pos::new(0, 0));
+                assert!(!board.get(Pos::new(0, 0)).unwrap().is_mine());
+                assert!(board.check_state() != Stage::Lose);
+                assert_eq!(board.mine_tiles, 40);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_opens_an_area() {
+        for seed in 0..50 {
+            let board = opened(rules(FirstClick::Zero), seed, Pos::new(7, 7));
+            assert_eq!(board.get(Pos::new(7, 7)).unwrap().value(), Some(0));
+            assert!(board.revealed() > 1);
+        }
+    }
+
+    #[test]
+    fn safe_3x3_clears_the_block_around_the_click() {
+        let wrap = Rules {
+            wrap: true,
+            ..rules(FirstClick::Safe3x3)
+        };
+        for rules in [rules(FirstClick::Safe3x3), wrap] {
+            for seed in 0..50 {
+                let board = opened(rules, seed, Pos::new(0, 0));
+                for row in -1..=1 {
+                    for col in -1..=1 {
+                        let Some(pos) = rules.wrap_pos(board.dim, Pos::new(row, col)) else {
+                            continue;
+                        };
+                        assert!(!board.get(pos).unwrap().is_mine(), "{pos:?}");
+                    }
+                }
+            }
+        }
+        // With wrap the corner's block reaches around to the far corner.
+        let board = opened(wrap, 0, Pos::new(0, 0));
+        assert!(!board.get(Pos::new(15, 15)).unwrap().is_mine());
+    }
+
+    #[test]
+    fn classic_moves_the_mine_and_keeps_the_count() {
+        // Only one tile is left without a mine, so the first click almost
+        // always lands on one and has to move it there.
+        for seed in 0..20 {
+            let mut board = Board::custom(Dim::new(3, 3), 8, rules(FirstClick::Classic));
+            board.open(&mut StdRng::seed_from_u64(seed), Pos::new(1, 1));
+            assert_eq!(board.mine_tiles, 8);
+            assert!(!board.get(Pos::new(1, 1)).unwrap().is_mine());
+            assert!(board.check_state() == Stage::Win);
+        }
+    }
+
+    #[test]
+    fn counts_openings_islands_and_three_bv() {
+        // The bottom two rows open at once, and the 2 between the mines is
+        // an island that needs a click of its own.
+        let mut board = board(&["*.*", "...", "..."], Rules::default());
+        assert_eq!(board.openings(), 1);
+        assert_eq!(board.islands(), 1);
+        assert_eq!(board.three_bv(), (2, 0));
+
+        board.mine(Pos::new(2, 0));
+        assert_eq!(board.three_bv(), (2, 1));
+        board.mine(Pos::new(0, 1));
+        assert_eq!(board.three_bv(), (2, 2));
+        assert!(board.check_state() == Stage::Win);
+    }
+
+    #[test]
+    fn changes_undo_and_redo() {
+        let mut board = board(&["*...", "....", "...*"], Rules::default());
+        let start = board.clone();
+        board.record();
+        board.flag(Pos::new(0, 0));
+        board.flag(Pos::new(0, 0));
+        assert!(board.take_change().is_none());
+
+        board.record();
+        board.mine(Pos::new(0, 3));
+        board.flag(Pos::new(0, 0));
+        board.flag(Pos::new(2, 3));
+        board.flag(Pos::new(2, 3));
+        let change = board.take_change().unwrap();
+        let played = board.clone();
+        // The opening, and the flag that was placed and kept.
+        assert_eq!(change.tiles.len(), 7);
+        assert!(board.take_change().is_none());
+
+        let redo = board.apply(change);
+        assert!(board == start);
+        board.apply(redo);
+        assert!(board == played);
+    }
+
+    #[test]
+    fn the_first_click_can_be_taken_back() {
+        let mut board = Board::new(Size::Normal, Rules::default());
+        let blank = board.clone();
+        board.record();
+        board.open(&mut StdRng::seed_from_u64(1), Pos::new(8, 8));
+        let change = board.take_change().unwrap();
+        let played = board.clone();
+
+        let redo = board.apply(change);
+        assert!(board == blank);
+        board.apply(redo);
+        assert!(board == played);
+    }
+
+    #[test]
+    fn clicking_an_exploded_mine_again_does_nothing() {
+        let rules = Rules {
+            lives: 3,
+            ..Rules::default()
+        };
+        let mut board = board(&["*..", "...", "..*"], rules);
+        board.mine(Pos::new(0, 0));
+        assert_eq!(board.exploded, 1);
+        assert_eq!(board.lives_left(), 2);
+
+        board.mine(Pos::new(0, 0));
+        assert_eq!(board.exploded, 1);
+        assert_eq!(board.revealed(), 0);
+        assert!(board.check_state() == Stage::Playing);
+    }
+
+    #[test]
+    fn clicking_an_exploded_mine_in_practice_does_nothing() {
+        let mut board = board(&["*..", "...", "..*"], Rules::default());
+        board.safe_death = true;
+        board.mine(Pos::new(0, 0));
+        board.mine(Pos::new(0, 0));
+        assert_eq!(board.exploded, 1);
+        assert!(board.check_state() == Stage::Playing);
+    }
+
+    /// The tiles a click on `pos` should reveal, found the obvious recursive way.
+    fn naive_reveal(board: &Board, pos: Pos, seen: &mut HashSet<Pos>) {
+        if !seen.insert(pos) || board.get(pos).unwrap().value() != Some(0) {
+            return;
+        }
+        for adj in board.neighbors(pos) {
+            naive_reveal(board, adj, seen);
+        }
+    }
+
+    #[test]
+    fn flood_fill_matches_a_recursive_reveal() {
+        for seed in 0..20 {
+            let mut board = Board::custom(Dim::new(60, 60), 180, rules(FirstClick::Zero));
+            let first = Pos::new(30, 30);
+            board.open(&mut StdRng::seed_from_u64(seed), first);
+            let mut expected = HashSet::new();
+            naive_reveal(&board, first, &mut expected);
+            let revealed: HashSet<Pos> = board
+                .dim
+                .positions()
+                .filter(|&pos| board.get(pos).unwrap().is_mined())
+                .collect();
+            assert_eq!(revealed, expected);
+            assert_eq!(board.revealed(), u32::try_from(expected.len()).unwrap());
+        }
+
+        // Far too deep for recursion, but one queue handles it.
+        let mut board = Board::custom(Dim::new(400, 400), 1, rules(FirstClick::Zero));
+        board.open(&mut StdRng::seed_from_u64(0), Pos::new(200, 200));
+        assert_eq!(board.revealed(), board.cells() - 1);
+        assert!(board.check_state() == Stage::Win);
+    }
+
+    #[test]
+    fn counters_match_a_rescan() {
+        let multi = Rules {
+            multi_mine: true,
+            ..Rules::default()
+        };
+        for rules in [Rules::default(), multi] {
+            for seed in 0..20 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut board = Board::new(Size::Normal, rules);
+                board.safe_death = true;
+                board.open(&mut rng, Pos::new(8, 8));
+                for _ in 0..40 {
+                    let pos = Pos::new(rng.gen_range(0..16), rng.gen_range(0..16));
+                    if rng.gen_ratio(1, 3) {
+                        board.flag(pos);
+                    } else {
+                        board.mine(pos);
+                    }
+
+                    let count = |f: fn(Tile) -> u32| board.tiles.iter().map(|&e| f(e)).sum::<u32>();
+                    assert_eq!(
+                        board.revealed,
+                        count(|e| u32::from(e.is_mined() && !e.is_mine()))
+                    );
+                    assert_eq!(
+                        board.zeros,
+                        count(|e| u32::from(e.is_mined() && e.value() == Some(0)))
+                    );
+                    assert_eq!(board.exploded, count(|e| u32::from(e.is_exploded())));
+                    assert_eq!(board.mine_tiles, count(|e| u32::from(e.is_mine())));
+                    assert_eq!(
+                        board.correct_flags,
+                        count(|e| u32::from(e.is_flagged() && e.flags() == e.mines()))
+                    );
+                    let exploded_mines = count(|e| {
+                        if e.is_exploded() {
+                            u32::from(e.mines())
+                        } else {
+                            0
+                        }
+                    });
+                    assert_eq!(
+                        board.flags,
+                        board.mines - count(|e| u32::from(e.flags())) - exploded_mines
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::{fs, path::Path, time::Duration};
+
+use crate::stopwatch::Stopwatch;
+use resource::resource;
+use sdl2::{
+    image::LoadTexture,
+    rect::Rect,
+    render::{Texture, TextureCreator, WindowCanvas},
+    ttf::Font,
+    video::WindowContext,
+};
+
+use crate::ui::{end_menu::Playfield, text::Text};
+
+use super::{
+    rules::{Rules, Topology},
+    tile::{Tile, TILE_SIZE},
+    world::{World, CHUNK, CHUNK_TILES},
+    Pos,
+};
+
+/// Where an unfinished endless game is kept between runs.
+pub const SAVE_PATH: &str = "endless.sav";
+pub const VIEW_WIDTH: i32 = 800;
+pub const VIEW_HEIGHT: i32 = 600;
+const CHUNK_PIXELS: i32 = CHUNK * TILE_SIZE;
+
+/// The SDL side of an endless game: a window onto the world that can be
+/// scrolled around.
+pub struct Endless<'a> {
+    pub world: World,
+    spritesheet: Texture<'a>,
+    flags_text: Text<'a>,
+    score_text: Text<'a>,
+    // The world pixel shown in the top left corner of the window.
+    camera: (i32, i32),
+    pub stopwatch: Stopwatch,
+}
+
+impl<'a> Endless<'a> {
+    /// Continues the saved game if there is one, or starts a new world.
+    pub fn new(
+        rules: Rules,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        font: &'a Font,
+    ) -> Endless<'a> {
+        let (world, elapsed) = fs::read_to_string(SAVE_PATH)
+            .ok()
+            .and_then(|text| World::load(&text).ok())
+            .unwrap_or_else(|| (World::new(rand::random(), rules), Duration::ZERO));
+        let center = world.first().unwrap_or(Pos::new(0, 0));
+        let mut stopwatch = Stopwatch::with_elapsed(elapsed);
+        if world.first().is_some() {
+            stopwatch.start();
+        }
+
+        Endless {
+            spritesheet: tex_creator
+                .load_texture_bytes(&resource!("res/spritesheet.png"))
+                .expect("Could not load spritesheet"),
+            flags_text: Text::new(
+                0,
+                0,
+                0,
+                0,
+                tex_creator,
+                &format!("Flags: {}", world.flags),
+                font,
+                u32::try_from(VIEW_WIDTH).unwrap(),
+                u32::try_from(VIEW_HEIGHT).unwrap(),
+            ),
+            score_text: Text::new(
+                0,
+                VIEW_HEIGHT - 30,
+                0,
+                0,
+                tex_creator,
+                &format!("Score: {}", world.revealed),
+                font,
+                u32::try_from(VIEW_WIDTH).unwrap(),
+                u32::try_from(VIEW_HEIGHT).unwrap(),
+            ),
+            camera: (
+                center.col * TILE_SIZE + TILE_SIZE / 2 - VIEW_WIDTH / 2,
+                center.row * TILE_SIZE + TILE_SIZE / 2 - VIEW_HEIGHT / 2,
+            ),
+            world,
+            stopwatch,
+        }
+    }
+
+    pub fn has_save() -> bool {
+        Path::new(SAVE_PATH).exists()
+    }
+
+    /// Saves the game so it can be continued from the main menu.
+    pub fn save(&self) -> Result<(), String> {
+        if self.world.first().is_none() {
+            return Ok(());
+        }
+        fs::write(SAVE_PATH, self.world.save(self.stopwatch.elapsed())).map_err(|e| e.to_string())
+    }
+
+    /// Forgets the saved game once it is over.
+    pub fn discard_save() -> Result<(), String> {
+        if !Endless::has_save() {
+            return Ok(());
+        }
+        fs::remove_file(SAVE_PATH).map_err(|e| e.to_string())
+    }
+
+    pub fn scroll(&mut self, dx: i32, dy: i32) {
+        self.camera.0 += dx;
+        self.camera.1 += dy;
+    }
+
+    pub fn mine(&mut self, pos: Pos) {
+        if self.world.first().is_none() {
+            self.stopwatch.start();
+        }
+        self.world.open(pos);
+        self.score_text
+            .set_text(&format!("Score: {}", self.world.revealed));
+    }
+
+    pub fn flag(&mut self, pos: Pos) {
+        self.world.flag(pos);
+        self.flags_text
+            .set_text(&format!("Flags: {}", self.world.flags));
+    }
+
+    /// The tile under the pixel at (x, y) of the window.
+    pub fn inside(&self, x: i32, y: i32) -> Option<Pos> {
+        ((0..VIEW_WIDTH).contains(&x) && (0..VIEW_HEIGHT).contains(&y)).then(|| {
+            Pos::new(
+                (y + self.camera.1).div_euclid(TILE_SIZE),
+                (x + self.camera.0).div_euclid(TILE_SIZE),
+            )
+        })
+    }
+}
+
+impl<'a> Playfield<'a> for Endless<'a> {
+    fn width(&self) -> i32 {
+        VIEW_WIDTH
+    }
+
+    fn height(&self) -> i32 {
+        VIEW_HEIGHT
+    }
+
+    /// Only draws the chunks that can be seen. Chunks that were never
+    /// generated are drawn as hidden tiles.
+    fn render(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        font: &Font,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        status: Option<bool>,
+    ) -> Result<(), String> {
+        let (x, y) = self.camera;
+        let hidden = [Tile::default(); CHUNK_TILES];
+        for row in y.div_euclid(CHUNK_PIXELS)..=(y + VIEW_HEIGHT - 1).div_euclid(CHUNK_PIXELS) {
+            for col in x.div_euclid(CHUNK_PIXELS)..=(x + VIEW_WIDTH - 1).div_euclid(CHUNK_PIXELS) {
+                let chunk = Pos::new(row, col);
+                let tiles = self.world.chunk(chunk).unwrap_or(&hidden);
+                for (i, tile) in tiles.iter().enumerate() {
+                    let i = i32::try_from(i).unwrap();
+                    tile.render(
+                        canvas,
+                        &self.spritesheet,
+                        Topology::Square,
+                        Rect::new(
+                            col * CHUNK_PIXELS + i % CHUNK * TILE_SIZE - x,
+                            row * CHUNK_PIXELS + i / CHUNK * TILE_SIZE - y,
+                            TILE_SIZE as u32,
+                            TILE_SIZE as u32,
+                        ),
+                        status,
+                    )?;
+                }
+            }
+        }
+
+        if status.is_none() {
+            self.flags_text.render(canvas, font, tex_creator)?;
+            self.score_text.render(canvas, font, tex_creator)?;
+        }
+
+        Ok(())
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "Score: {} | Time: {}",
+            self.world.revealed,
+            self.stopwatch.elapsed().as_secs()
+        )
+    }
+
+    fn name(&self) -> String {
+        "Endless".to_string()
+    }
+}
@@ -0,0 +1,609 @@
+use std::{fmt, fs, path::Path};
+
+use super::{
+    board::Board,
+    rules::{FirstClick, Neighborhood, Rules, Topology},
+    tile::Tile,
+    Dim, Pos,
+};
+
+/// Where boards are exported to, and the first place to look for them.
+pub const BOARDS_DIR: &str = "boards";
+
+/// The most tiles a layout can have. Sizes come from files and codes, so they
+/// are checked before anything is allocated for them.
+pub const MAX_CELLS: usize = 1 << 22;
+
+/// Changes whenever share codes are laid out differently.
+const CODE_VERSION: u8 = 1;
+/// How the tiles of a code are packed: bit by bit, or as run lengths.
+const PACKED_BITS: u8 = 0;
+const PACKED_RUNS: u8 = 1;
+/// The URL safe base64 alphabet, so codes can be pasted anywhere.
+const CODE_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A board with every mine already placed, for puzzles and regression boards
+/// that have to come out the same every time.
+///
+/// In text a layout starts with a `<width>x<height>` line followed by one
+/// line per row: `.` for a safe tile, `*` for a mine, `2` or `3` for a tile
+/// holding that many mines and `#` for a wall.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Layout {
+    pub name: String,
+    pub dim: Dim,
+    /// The rules the board is meant for. Text files leave them out, so those
+    /// are played with whatever is picked in the menu.
+    pub rules: Option<Rules>,
+    // Row-major, the number of mines on each tile or `None` for a wall.
+    tiles: Vec<Option<u8>>,
+}
+
+impl Layout {
+    pub fn parse(name: &str, text: &str) -> Result<Layout, String> {
+        let mut lines = text.lines().filter(|e| !e.trim().is_empty());
+        let header = lines
+            .next()
+            .ok_or_else(|| format!("Board \"{name}\" is empty"))?;
+        let (cols, rows) = header
+            .trim()
+            .split_once('x')
+            .and_then(|(cols, rows)| Some((cols.parse().ok()?, rows.parse().ok()?)))
+            .ok_or_else(|| {
+                format!("Board \"{name}\" has to start with its size, e.g. 9x9, not \"{header}\"")
+            })?;
+        let dim = checked_dim(rows, cols).ok_or_else(|| {
+            format!(
+                "Board \"{name}\" can't be {cols}x{rows}, it has to have 1 to {MAX_CELLS} tiles \
+                 and at most {} on a side",
+                u16::MAX
+            )
+        })?;
+
+        let mut tiles = Vec::with_capacity(dim.cells());
+        let mut rows = 0;
+        for (i, line) in lines.enumerate() {
+            let row = line
+                .trim()
+                .chars()
+                .map(|c| match c {
+                    '.' => Ok(Some(0)),
+                    '*' => Ok(Some(1)),
+                    '2' | '3' => Ok(Some(u8::try_from(c.to_digit(10).unwrap()).unwrap())),
+                    '#' => Ok(None),
+                    _ => Err(format!(
+                        "Unexpected '{c}' on line {} of board \"{name}\"",
+                        i + 2
+                    )),
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            if row.len() != usize::try_from(dim.cols).unwrap() {
+                return Err(format!(
+                    "Line {} of board \"{name}\" should be {} tiles long",
+                    i + 2,
+                    dim.cols
+                ));
+            }
+            tiles.extend(row);
+            rows += 1;
+        }
+        if rows != dim.rows {
+            return Err(format!(
+                "Board \"{name}\" should have {} rows, not {rows}",
+                dim.rows
+            ));
+        }
+        Ok(Layout {
+            name: name.to_string(),
+            dim,
+            rules: None,
+            tiles,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Layout, String> {
+        let name = path.file_stem().map_or_else(
+            || path.display().to_string(),
+            |e| e.to_string_lossy().into_owned(),
+        );
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Layout::parse(&name, &text)
+    }
+
+    /// Writes the layout to `boards/<name>.txt`, returning the path.
+    pub fn save(&self) -> Result<String, String> {
+        fs::create_dir_all(BOARDS_DIR).map_err(|e| e.to_string())?;
+        let path = format!("{BOARDS_DIR}/{}.txt", self.name);
+        fs::write(&path, self.to_string()).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    /// Where the mines of `board` are. Before the first click there are none.
+    pub fn of(name: &str, board: &Board) -> Layout {
+        Layout {
+            name: name.to_string(),
+            dim: board.dim,
+            rules: Some(board.rules),
+            tiles: board
+                .tiles()
+                .iter()
+                .map(|e| (!e.is_wall()).then(|| e.mines()))
+                .collect(),
+        }
+    }
+
+    pub fn mines(&self) -> u32 {
+        self.tiles.iter().flatten().map(|&e| u32::from(e)).sum()
+    }
+
+    /// A board with the mines of this layout, ready to be clicked on. There
+    /// is no first click protection, as the mines are already placed. Boards
+    /// without rules of their own use `rules`, with multi-mine rules turned on
+    /// if any tile holds several mines.
+    pub fn board(&self, rules: Rules) -> Board {
+        let rules = self.rules_or(rules);
+        let tiles = self
+            .tiles
+            .iter()
+            .map(|&e| {
+                let mut tile = Tile::default();
+                match e {
+                    Some(mines) => (0..mines).for_each(|_| tile.add_mine()),
+                    None => tile.set_wall(),
+                }
+                tile
+            })
+            .collect();
+        Board::fixed(self.dim, tiles, rules)
+    }
+
+    fn rules_or(&self, rules: Rules) -> Rules {
+        let mut rules = self.rules.unwrap_or(rules);
+        if self.tiles.iter().flatten().any(|&e| e > 1) {
+            rules.multi_mine = true;
+        }
+        rules
+    }
+
+    /// How many mines are on `pos`, or `None` for a wall.
+    pub fn get(&self, pos: Pos) -> Option<u8> {
+        self.tiles[self.dim.index(pos).unwrap()]
+    }
+
+    /// A short code holding the mines, the size and the rules, which can be
+    /// pasted into the menu to play the same board.
+    ///
+    /// After a version byte come the rules, the width and height as two bytes
+    /// each and how the tiles are packed. Every tile is one bit for a mine,
+    /// or two bits with multi-mine rules, and boards with walls have one more
+    /// bit in front of every tile. Those bits are either written as they are
+    /// or as the lengths of their runs, whichever is shorter.
+    pub fn code(&self) -> String {
+        let rules = self.rules_or(Rules::default());
+        let walls = self.tiles.contains(&None);
+        let mut bytes = vec![
+            CODE_VERSION,
+            index(&FirstClick::ALL, &rules.first_click)
+                | index(&Topology::ALL, &rules.topology) << 2
+                | u8::from(rules.wrap) << 3
+                | index(&Neighborhood::ALL, &rules.neighborhood) << 4
+                | u8::from(rules.multi_mine) << 6
+                | u8::from(walls) << 7,
+            rules.lives,
+        ];
+        for n in [self.dim.cols, self.dim.rows] {
+            bytes.extend(u16::try_from(n).unwrap().to_be_bytes());
+        }
+
+        let mut bits = vec![];
+        for &tile in &self.tiles {
+            if walls {
+                bits.push(tile.is_none());
+            }
+            let mines = tile.unwrap_or(0);
+            if rules.multi_mine {
+                bits.push(mines & 2 != 0);
+            }
+            bits.push(mines & 1 != 0);
+        }
+        let runs = run_lengths(&bits);
+        let packed = if runs.len() < bits.len() {
+            bytes.push(PACKED_RUNS);
+            runs
+        } else {
+            bytes.push(PACKED_BITS);
+            bits
+        };
+        bytes.extend(packed.chunks(8).map(|chunk| {
+            chunk
+                .iter()
+                .zip((0..8).rev())
+                .fold(0, |byte, (&bit, shift)| byte | u8::from(bit) << shift)
+        }));
+        base64(&bytes)
+    }
+
+    /// Whether `c` can be part of a code.
+    pub fn is_code_char(c: char) -> bool {
+        c.is_ascii() && CODE_ALPHABET.contains(&(c as u8))
+    }
+
+    /// Reads a code made by `code`. Spaces and line breaks are ignored.
+    pub fn from_code(code: &str) -> Result<Layout, String> {
+        let invalid = || "Not a valid board code".to_string();
+        let digits = code
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| {
+                CODE_ALPHABET
+                    .iter()
+                    .position(|&e| char::from(e) == c)
+                    .map(|e| u32::try_from(e).unwrap())
+            })
+            .collect::<Option<Vec<u32>>>()
+            .ok_or_else(invalid)?;
+        let bytes: Vec<u8> = digits
+            .chunks(4)
+            .flat_map(|chunk| {
+                let n = chunk
+                    .iter()
+                    .zip([18, 12, 6, 0])
+                    .fold(0, |n, (&digit, shift)| n | digit << shift);
+                (0..chunk.len() - 1).map(move |i| n.to_be_bytes()[i + 1])
+            })
+            .collect();
+
+        let [version, flags, lives, c0, c1, r0, r1, packing, ref tiles @ ..] = bytes[..] else {
+            return Err(invalid());
+        };
+        if version != CODE_VERSION {
+            return Err("The code is from a different version of the game".to_string());
+        }
+        let rules = Rules {
+            first_click: pick(&FirstClick::ALL, flags & 3).ok_or_else(invalid)?,
+            topology: pick(&Topology::ALL, flags >> 2 & 1).ok_or_else(invalid)?,
+            wrap: flags & 1 << 3 != 0,
+            neighborhood: pick(&Neighborhood::ALL, flags >> 4 & 3).ok_or_else(invalid)?,
+            multi_mine: flags & 1 << 6 != 0,
+            lives,
+        };
+        let walls = flags & 1 << 7 != 0;
+        let dim = checked_dim(
+            i32::from(u16::from_be_bytes([r0, r1])),
+            i32::from(u16::from_be_bytes([c0, c1])),
+        )
+        .ok_or_else(invalid)?;
+        if lives == 0 || !rules.neighborhood.supports(rules.topology) {
+            return Err(invalid());
+        }
+
+        let bits_per_tile = 1 + usize::from(walls) + usize::from(rules.multi_mine);
+        let mut bits = tiles
+            .iter()
+            .flat_map(|&byte| (0..8).rev().map(move |shift| byte >> shift & 1 != 0));
+        let bits = match packing {
+            PACKED_BITS => Some(bits.take(dim.cells() * bits_per_tile).collect()),
+            PACKED_RUNS => from_run_lengths(&mut bits, dim.cells() * bits_per_tile),
+            _ => None,
+        }
+        .ok_or_else(invalid)?;
+        if bits.len() != dim.cells() * bits_per_tile {
+            return Err(invalid());
+        }
+        let mut bits = bits.into_iter();
+        let mut next = || bits.next().ok_or_else(invalid);
+        let mut layout = Layout {
+            name: "code".to_string(),
+            dim,
+            rules: Some(rules),
+            tiles: Vec::with_capacity(dim.cells()),
+        };
+        for _ in 0..dim.cells() {
+            let wall = walls && next()?;
+            let high = rules.multi_mine && next()?;
+            let low = next()?;
+            let mines = u8::from(high) << 1 | u8::from(low);
+            layout.tiles.push((!wall).then_some(mines));
+        }
+        Ok(layout)
+    }
+}
+
+/// `bytes` in the code alphabet, without padding.
+fn base64(bytes: &[u8]) -> String {
+    bytes
+        .chunks(3)
+        .flat_map(|chunk| {
+            let n = chunk
+                .iter()
+                .zip([16, 8, 0])
+                .fold(0u32, |n, (&byte, shift)| n | u32::from(byte) << shift);
+            (0..=chunk.len()).map(move |i| CODE_ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char)
+        })
+        .collect()
+}
+
+/// Writes `bits` as the value of the first bit and then the length of every
+/// run of equal bits, each as an Elias gamma code. Long runs of safe tiles
+/// and walls take only a few bits that way.
+fn run_lengths(bits: &[bool]) -> Vec<bool> {
+    let mut out = vec![];
+    let Some(&first) = bits.first() else {
+        return out;
+    };
+    out.push(first);
+    let mut rest = bits;
+    while let Some(&bit) = rest.first() {
+        let run = rest.iter().take_while(|&&e| e == bit).count();
+        let width = usize::BITS - run.leading_zeros();
+        out.extend((1..width).map(|_| false));
+        out.extend((0..width).rev().map(|shift| run >> shift & 1 != 0));
+        rest = &rest[run..];
+    }
+    out
+}
+
+/// Reads `len` bits written by `run_lengths`, or `None` if they don't add up.
+fn from_run_lengths(bits: &mut impl Iterator<Item = bool>, len: usize) -> Option<Vec<bool>> {
+    let mut out = Vec::with_capacity(len);
+    let mut bit = bits.next()?;
+    while out.len() < len {
+        let mut zeros = 0;
+        while !bits.next()? {
+            zeros += 1;
+            if zeros >= usize::BITS {
+                return None;
+            }
+        }
+        let run =
+            (0..zeros).try_fold(1usize, |run, _| Some(run << 1 | usize::from(bits.next()?)))?;
+        if out.len() + run > len {
+            return None;
+        }
+        out.extend((0..run).map(|_| bit));
+        bit = !bit;
+    }
+    Some(out)
+}
+
+/// `rows` by `cols` if that is a board that can be played on. Codes keep
+/// each side in two bytes, so neither can be longer than `u16::MAX`.
+pub(super) fn checked_dim(rows: i32, cols: i32) -> Option<Dim> {
+    let cells = usize::try_from(rows.checked_mul(cols)?).ok()?;
+    let side = |n: i32| n > 0 && u16::try_from(n).is_ok();
+    (side(rows) && side(cols) && cells <= MAX_CELLS).then(|| Dim::new(rows, cols))
+}
+
+/// Where `e` is in `all`, as written in a code.
+fn index<T: PartialEq>(all: &[T], e: &T) -> u8 {
+    u8::try_from(all.iter().position(|a| a == e).unwrap()).unwrap()
+}
+
+fn pick<T: Copy>(all: &[T], i: u8) -> Option<T> {
+    all.get(usize::from(i)).copied()
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}x{}", self.dim.cols, self.dim.rows)?;
+        for row in 0..self.dim.rows {
+            for col in 0..self.dim.cols {
+                let c = match self.get(Pos::new(row, col)) {
+                    Some(0) => '.',
+                    Some(1) => '*',
+                    Some(mines) => char::from_digit(u32::from(mines), 10).unwrap(),
+                    None => '#',
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::super::board::Size;
+    use super::*;
+
+    const TEXT: &str = "4x3\n.*..\n#..2\n...*\n";
+
+    #[test]
+    fn parses_and_writes_the_same_text() {
+        let layout = Layout::parse("test", TEXT).unwrap();
+        assert_eq!(layout.dim, Dim::new(3, 4));
+        assert_eq!(layout.get(Pos::new(0, 1)), Some(1));
+        assert_eq!(layout.get(Pos::new(1, 0)), None);
+        assert_eq!(layout.get(Pos::new(1, 3)), Some(2));
+        assert_eq!(layout.mines(), 4);
+        assert_eq!(layout.to_string(), TEXT);
+    }
+
+    #[test]
+    fn ignores_blank_lines_and_indentation() {
+        let layout = Layout::parse("test", "\n  2x2\n\n .*\n  ..\n").unwrap();
+        assert_eq!(layout.to_string(), "2x2\n.*\n..\n");
+    }
+
+    #[test]
+    fn rejects_bad_boards() {
+        for text in [
+            "",
+            "9 by 9\n",
+            "0x3\n",
+            "2x2\n..\n.\n",
+            "2x2\n..\n",
+            "2x2\n..\n..\n..\n",
+            "2x2\n.x\n..\n",
+        ] {
+            assert!(Layout::parse("test", text).is_err(), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_sizes_that_are_too_large() {
+        assert!(Layout::parse("test", "50000x50000\n").is_err());
+        assert!(Layout::parse("test", "2147483647x2\n").is_err());
+        assert!(Layout::parse("test", "-3x-3\n").is_err());
+    }
+
+    #[test]
+    fn sides_fit_in_a_code() {
+        let row = |cols: usize| format!("{cols}x1\n{}*\n", ".".repeat(cols - 1));
+        let widest = Layout::parse("wide", &row(65_535)).unwrap();
+        assert_eq!(
+            Layout::from_code(&widest.code()).unwrap().to_string(),
+            widest.to_string()
+        );
+        assert!(Layout::parse("wide", &row(65_536)).is_err());
+        assert!(Layout::parse("tall", "1x70000\n").is_err());
+    }
+
+    #[test]
+    fn plays_with_multi_mine_rules_when_tiles_share_mines() {
+        let layout = Layout::parse("test", TEXT).unwrap();
+        let board = layout.board(Rules::default());
+        assert!(board.rules.multi_mine);
+        assert_eq!(board.mines, 4);
+        assert!(!board.first_move);
+    }
+
+    /// A layout with the mines of a random `size` board.
+    fn random(size: Size, rules: Rules, seed: u64) -> Layout {
+        let mut board = Board::new(size, rules);
+        board.generate_mines(&mut StdRng::seed_from_u64(seed), Pos::new(0, 0));
+        Layout::of("code", &board)
+    }
+
+    #[test]
+    fn codes_come_back_the_same() {
+        let rules = [
+            Rules::default(),
+            Rules {
+                topology: Topology::Hex,
+                wrap: true,
+                lives: 3,
+                ..Rules::default()
+            },
+            Rules {
+                multi_mine: true,
+                neighborhood: Neighborhood::ALL[1],
+                ..Rules::default()
+            },
+        ];
+        for (rules, seed) in rules.into_iter().zip(0..) {
+            for size in [Size::Small, Size::Normal, Size::Large] {
+                let layout = random(size, rules, seed);
+                assert_eq!(Layout::from_code(&layout.code()), Ok(layout));
+            }
+        }
+    }
+
+    #[test]
+    fn codes_with_walls_come_back_the_same() {
+        let mut layout = Layout::parse("code", TEXT).unwrap();
+        layout.rules = Some(Rules::default());
+        let mut expected = layout.clone();
+        expected.rules = Some(layout.rules_or(Rules::default()));
+        assert_eq!(Layout::from_code(&layout.code()), Ok(expected));
+    }
+
+    #[test]
+    fn codes_ignore_spaces_and_line_breaks() {
+        let layout = random(Size::Normal, Rules::default(), 7);
+        let code = layout.code();
+        let (a, b) = code.split_at(10);
+        assert_eq!(Layout::from_code(&format!(" {a}\n {b} ")), Ok(layout));
+    }
+
+    #[test]
+    fn codes_of_sparse_boards_are_short() {
+        let mut text = "200x200\n".to_string();
+        for row in 0..200 {
+            let line: String = (0..200)
+                .map(|col| if row == col { '*' } else { '.' })
+                .collect();
+            text.push_str(&line);
+            text.push('\n');
+        }
+        let layout = Layout::parse("code", &text).unwrap();
+        let code = layout.code();
+        // The plain bitmap would take over 6600 characters.
+        assert!(code.len() < 600, "{}", code.len());
+        assert_eq!(Layout::from_code(&code).unwrap().to_string(), text);
+    }
+
+    #[test]
+    fn codes_are_never_longer_than_the_plain_bitmap() {
+        for seed in 0..20 {
+            let layout = random(Size::Large, Rules::default(), seed);
+            // 8 header bytes and one bit per tile.
+            let plain = (8 * 8 + layout.dim.cells()).div_ceil(6);
+            assert!(layout.code().len() <= plain);
+        }
+    }
+
+    #[test]
+    fn run_lengths_come_back_the_same() {
+        let bits: Vec<bool> = [0, 0, 0, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0]
+            .iter()
+            .map(|&e| e == 1)
+            .collect();
+        let runs = run_lengths(&bits);
+        assert_eq!(
+            from_run_lengths(&mut runs.into_iter(), bits.len()),
+            Some(bits)
+        );
+        assert_eq!(run_lengths(&[]), vec![]);
+    }
+
+    #[test]
+    fn rejects_bad_codes() {
+        let code = random(Size::Small, Rules::default(), 1).code();
+        for bad in [
+            "",
+            "A",
+            "not a code!",
+            &code[..code.len() - 4],
+            &format!("B{}", &code[1..]),
+        ] {
+            assert!(Layout::from_code(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_codes_with_huge_sizes() {
+        // 50000x50000, which would overflow when counting the tiles.
+        let [c0, c1] = 50000u16.to_be_bytes();
+        for packing in [PACKED_BITS, PACKED_RUNS] {
+            let bytes = [
+                CODE_VERSION,
+                0,
+                1,
+                c0,
+                c1,
+                c0,
+                c1,
+                packing,
+                0xff,
+                0xff,
+                0xff,
+            ];
+            assert!(Layout::from_code(&base64(&bytes)).is_err());
+        }
+        let [c0, c1] = 0u16.to_be_bytes();
+        let bytes = [CODE_VERSION, 0, 1, c0, c1, c0, c1, PACKED_BITS, 0];
+        assert!(Layout::from_code(&base64(&bytes)).is_err());
+    }
+
+    #[test]
+    fn takes_the_mines_of_a_board() {
+        let layout = Layout::parse("test", TEXT).unwrap();
+        let copy = Layout::of("test", &layout.board(Rules::default()));
+        assert_eq!(copy.to_string(), TEXT);
+    }
+}
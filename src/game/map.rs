@@ -1,269 +1,468 @@
-use crate::stopwatch::Stopwatch;
-use rand::{rngs::ThreadRng, Rng};
+use std::{collections::VecDeque, time::Duration};
+
+use crate::{
+    agent::{
+        solver::{self, Verdict},
+        View,
+    },
+    date::Date,
+    stopwatch::Stopwatch,
+};
+use rand::{rngs::StdRng, SeedableRng};
 use resource::resource;
 use sdl2::{
     image::LoadTexture,
+    pixels::Color,
+    rect::Rect,
     render::{Texture, TextureCreator, WindowCanvas},
     ttf::Font,
     video::WindowContext,
 };
 
-use crate::ui::text::Text;
+use crate::ui::{end_menu::Playfield, text::Text};
 
 use super::{
-    tile::{Tile, TILE_SIZE},
-    Coords, Stage,
+    board::{Board, Change, Size},
+    layout::Layout,
+    mask::Mask,
+    puzzle::Puzzle,
+    replay::{Click, Move, Replay},
+    rules::{Neighborhood, Rules, Topology},
+    stats::{seconds, Clicks, Stats},
+    tile::{Tile, HEX_HEIGHT, HEX_ROW_STEP, TILE_SIZE},
+    Dim, Pos, Stage,
 };
 
+/// How many actions can be undone before the oldest ones are forgotten.
+const MAX_UNDO: usize = 1000;
+
+/// The counts of a game that undo takes back along with the board.
 #[derive(Clone, Copy)]
-pub enum Size {
-    Small,
-    Normal,
-    Large,
+struct Counts {
+    clicks: Clicks,
+    mistakes: u32,
+    forced_guesses: u32,
 }
 
-impl ToString for Size {
-    fn to_string(&self) -> String {
-        match *self {
-            Self::Small => "Small",
-            Self::Normal => "Normal",
-            Self::Large => "Large",
-        }
-        .to_string()
-    }
+/// An action that can be undone: what it changed on the board, and the
+/// counts and number of moves from before it.
+struct Undo {
+    change: Change,
+    counts: Counts,
+    moves: usize,
+}
+
+/// An action that was undone: what to change on the board to do it again,
+/// and the counts and moves it had.
+struct Redo {
+    change: Change,
+    counts: Counts,
+    moves: Vec<Move>,
 }
 
 pub struct Map<'a> {
     pub size: Size,
-    pub dim: Coords<i32>,
-    map: Vec<Vec<Tile>>,
-    lost: bool,
-    pub first_move: bool,
+    pub board: Board,
     spritesheet: Texture<'a>,
-    mines: u8,
-    flags: u8,
     flags_text: Text<'a>,
     time_text: Text<'a>,
+    // Only shown when playing with more than one life.
+    lives_text: Option<Text<'a>>,
+    // Says how the last mine hit in a practice game is judged.
+    verdict_text: Text<'a>,
     pub stopwatch: Stopwatch,
-    pub percentage: u32,
+    /// What the mines were laid out from.
+    pub seed: u64,
+    rng: StdRng,
+    /// Every reveal, chord and flag the player made.
+    clicks: Clicks,
+    // The same clicks in order, for the replay.
+    moves: Vec<Move>,
+    /// Set when this is the daily challenge of that day.
+    pub daily: Option<Date>,
+    /// Set to the name of the layout the board was loaded from, which the
+    /// game goes by instead of its size.
+    pub layout: Option<String>,
+    // The actions that can still be undone, oldest first, and the ones that
+    // were undone and can be redone.
+    history: VecDeque<Undo>,
+    future: Vec<Redo>,
+    /// Set once anything was undone, which keeps the game off the scores.
+    pub undone: bool,
+    /// Mines hit in a practice game that a solver could have avoided.
+    pub mistakes: u32,
+    /// Mines hit in a practice game when there was nothing left but to guess.
+    pub forced_guesses: u32,
 }
 
 impl<'a> Map<'a> {
     pub fn new(
         size: Size,
+        rules: Rules,
+        mask: Option<&Mask>,
         tex_creator: &'a TextureCreator<WindowContext>,
         font: &'a Font,
     ) -> Map<'a> {
-        let dim = match size {
-            Size::Small => (9, 9),
-            Size::Normal => (16, 16),
-            Size::Large => (18, 30),
-        };
-        let mines = match size {
-            Size::Small => 10,
-            Size::Normal => 40,
-            Size::Large => 99,
+        let board = match mask {
+            Some(mask) => Board::masked(mask, mask.mines(size), rules),
+            None => Board::new(size, rules),
         };
+        Map::with_board(size, board, tex_creator, font)
+    }
+
+    /// A board loaded from `layout`, with its mines where the layout puts
+    /// them.
+    pub fn fixed(
+        layout: &Layout,
+        rules: Rules,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        font: &'a Font,
+    ) -> Map<'a> {
+        // The size is only used for the name, which the layout's replaces.
+        let mut map = Map::with_board(Size::Normal, layout.board(rules), tex_creator, font);
+        map.layout = Some(layout.name.clone());
+        map
+    }
+
+    /// A puzzle, starting with its tiles revealed and flagged.
+    pub fn puzzle(
+        puzzle: &Puzzle,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        font: &'a Font,
+    ) -> Map<'a> {
+        let mut map = Map::with_board(Size::Normal, puzzle.board(), tex_creator, font);
+        map.layout = Some(puzzle.layout.name.clone());
+        map
+    }
+
+    fn with_board(
+        size: Size,
+        board: Board,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        font: &'a Font,
+    ) -> Map<'a> {
+        let rules = board.rules;
+        let (width, height) = Self::pixel_size(board.dim, rules);
+        let seed = rand::random();
         Map {
             size,
-            dim,
-            map: {
-                let mut map = vec![];
-                for _ in 0..dim.1 {
-                    let mut row = vec![];
-                    for _ in 0..dim.0 {
-                        row.push(Tile::new(Some(0)));
-                    }
-                    map.push(row);
-                }
-                map
-            },
-            lost: false,
-            first_move: true,
             spritesheet: tex_creator
-                .load_texture_bytes(&resource!("res/spritesheet.png"))
+                .load_texture_bytes(&match rules.topology {
+                    Topology::Square => resource!("res/spritesheet.png"),
+                    Topology::Hex => resource!("res/hex_spritesheet.png"),
+                })
                 .expect("Could not load spritesheet"),
-            mines,
-            flags: mines,
             flags_text: Text::new(
                 0,
                 0,
                 0,
                 0,
                 tex_creator,
-                &format!("Flags: {mines}"),
+                &format!("Flags: {}", board.flags),
                 font,
-                u32::try_from(dim.1 * TILE_SIZE).unwrap(),
-                u32::try_from(dim.0 * TILE_SIZE).unwrap(),
+                u32::try_from(width).unwrap(),
+                u32::try_from(height).unwrap(),
             ),
             time_text: Text::new(
                 0,
-                match size {
-                    Size::Small => 9,
-                    Size::Normal => 16,
-                    Size::Large => 18,
-                } * TILE_SIZE
-                    - 30,
+                height - 30,
                 0,
                 0,
                 tex_creator,
                 "Time: 0",
                 font,
-                u32::try_from(dim.1 * TILE_SIZE).unwrap(),
-                u32::try_from(dim.0 * TILE_SIZE).unwrap(),
+                u32::try_from(width).unwrap(),
+                u32::try_from(height).unwrap(),
+            ),
+            lives_text: (rules.lives > 1).then(|| {
+                Text::new(
+                    0,
+                    30,
+                    0,
+                    0,
+                    tex_creator,
+                    &format!("Lives: {}", board.lives_left()),
+                    font,
+                    u32::try_from(width).unwrap(),
+                    u32::try_from(height).unwrap(),
+                )
+            }),
+            verdict_text: Text::new(
+                0,
+                60,
+                0,
+                0,
+                tex_creator,
+                "Practice",
+                font,
+                u32::try_from(width).unwrap(),
+                u32::try_from(height).unwrap(),
             ),
+            board,
             stopwatch: Stopwatch::default(),
-            percentage: 0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            clicks: Clicks::default(),
+            moves: vec![],
+            daily: None,
+            layout: None,
+            history: VecDeque::new(),
+            future: vec![],
+            undone: false,
+            mistakes: 0,
+            forced_guesses: 0,
         }
     }
 
-    fn get(&self, pos: Coords<i32>) -> Option<&Tile> {
-        if pos.0 > self.dim.0 - 1 || pos.1 > self.dim.1 - 1 {
-            None
-        } else {
-            Some(&self.map[usize::try_from(pos.1).unwrap()][usize::try_from(pos.0).unwrap()])
-        }
+    /// The daily challenge: a normal board laid out from the date alone. The
+    /// centre is opened up front so the first click can not change the board,
+    /// and the clock starts with the player's first move. The opening is kept
+    /// as the first move at no time, so replays of the day start from it too,
+    /// but it is not one of the player's clicks.
+    pub fn daily(
+        date: Date,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        font: &'a Font,
+    ) -> Map<'a> {
+        let mut map = Map::new(Size::Normal, Rules::default(), None, tex_creator, font);
+        map.reseed(date.seed());
+        map.daily = Some(date);
+        let dim = map.board.dim;
+        let centre = Pos::new(dim.rows / 2, dim.cols / 2);
+        map.moves.push(Move {
+            time: Duration::ZERO,
+            click: Click::Reveal,
+            pos: centre,
+        });
+        map.board.open(&mut map.rng, centre);
+        map
     }
 
-    fn get_adjacent_tiles(&self, pos: Coords<i32>) -> Vec<Coords<i32>> {
-        let mut adjacent = vec![];
-        let (col, row) = pos;
-        if col > 0 {
-            if row > 0 {
-                adjacent.push((col - 1, row - 1));
-            }
-            adjacent.push((col - 1, row));
-            if row < self.dim.1 - 1 {
-                adjacent.push((col - 1, row + 1));
-            }
-        }
-        if col < self.dim.0 - 1 {
-            if row > 0 {
-                adjacent.push((col + 1, row - 1));
-            }
-            adjacent.push((col + 1, row));
-            if row < self.dim.1 - 1 {
-                adjacent.push((col + 1, row + 1));
-            }
-        }
-        if row > 0 {
-            adjacent.push((col, row - 1));
+    /// Makes the mines come out the same every time for `seed`. Has to be
+    /// called before the first click.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// The state of the board, or `TimeOut` once a countdown has run out.
+    pub fn check_state(&self) -> Stage {
+        match self.board.check_state() {
+            Stage::Playing if self.stopwatch.expired() => Stage::TimeOut,
+            state => state,
         }
-        if row < self.dim.1 - 1 {
-            adjacent.push((col, row + 1));
+    }
+
+    /// Space around the board for the ghost tiles drawn when the edges wrap.
+    fn margin(dim: Dim, rules: Rules) -> (i32, i32) {
+        if !rules.wrap {
+            return (0, 0);
         }
+        let row = match rules.topology {
+            _ if !rules.wraps_rows(dim) => 0,
+            Topology::Square => TILE_SIZE,
+            Topology::Hex => HEX_ROW_STEP,
+        };
+        (TILE_SIZE, row)
+    }
 
-        adjacent
+    fn pixel_size(dim: Dim, rules: Rules) -> (i32, i32) {
+        let (margin_x, margin_y) = Self::margin(dim, rules);
+        let (width, height) = match rules.topology {
+            Topology::Square => (dim.cols * TILE_SIZE, dim.rows * TILE_SIZE),
+            Topology::Hex => (
+                dim.cols * TILE_SIZE + if dim.rows > 1 { TILE_SIZE / 2 } else { 0 },
+                (dim.rows - 1) * HEX_ROW_STEP + HEX_HEIGHT,
+            ),
+        };
+        (width + 2 * margin_x, height + 2 * margin_y)
     }
 
-    pub fn generate_mines(&mut self, rng: &mut ThreadRng, x: i32, y: i32) {
-        let (mut rand_col, mut rand_row): Coords<i32>;
+    pub fn width(&self) -> i32 {
+        Self::pixel_size(self.board.dim, self.board.rules).0
+    }
 
-        for _ in 0..self.mines {
-            loop {
-                rand_col = rng.gen_range(0..self.dim.0);
-                rand_row = rng.gen_range(0..self.dim.1);
-                if !(self.get((rand_col, rand_row)).unwrap().is_mine
-                    || ((x - rand_col).abs() < 2 && (y - rand_row).abs() < 2))
-                {
-                    break;
-                }
-            }
+    pub fn height(&self) -> i32 {
+        Self::pixel_size(self.board.dim, self.board.rules).1
+    }
+
+    /// Where `pos` is drawn on the screen. Positions just outside of the
+    /// board are the ghost tiles.
+    fn tile_rect(&self, pos: Pos) -> Rect {
+        let (x, y) = tile_corner(self.board.dim, self.board.rules, pos);
+        let height = match self.board.rules.topology {
+            Topology::Square => TILE_SIZE as u32,
+            Topology::Hex => HEX_HEIGHT as u32,
+        };
+        Rect::new(x, y, TILE_SIZE as u32, height)
+    }
+
+    pub fn mine(&mut self, pos: Pos) {
+        if self.check_state() != Stage::Playing {
+            return;
+        }
+        if !self.stopwatch.is_running() {
+            self.stopwatch.start();
+        }
+        let (counts, moves) = (self.counts(), self.moves.len());
+        let click = if self.board.get(pos).is_some_and(Tile::is_mined) {
+            self.clicks.chord += 1;
+            Click::Chord
+        } else {
+            self.clicks.left += 1;
+            Click::Reveal
+        };
+        self.moves.push(Move {
+            time: self.stopwatch.elapsed(),
+            click,
+            pos,
+        });
+        let exploded = self.board.exploded;
+        self.board.record();
+        self.board.open(&mut self.rng, pos);
+        let change = self.board.take_change();
 
-            self.map[usize::try_from(rand_row).unwrap()][usize::try_from(rand_col).unwrap()]
-                .set_mine();
+        // A chord can set off several mines at once.
+        let exploded = self.board.exploded - exploded;
+        if exploded > 0 && self.board.safe_death {
+            // Judging needs the board from before, which is only put back
+            // together when a mine went off.
+            let mut before = self.board.clone();
+            before.apply(change.clone().unwrap());
+            self.judge(&before);
+        } else if exploded > 0 && self.board.check_state() != Stage::Lose {
+            self.stopwatch.penalize(Rules::LIFE_PENALTY * exploded);
         }
+        self.remember(change, counts, moves);
     }
 
-    pub fn generate_tiles(&mut self) {
-        for (i, row) in self.map.clone().iter().enumerate() {
-            for (j, tile) in row.iter().enumerate() {
-                if tile.is_mine {
-                    continue;
-                }
-                let adjacent =
-                    self.get_adjacent_tiles((i32::try_from(j).unwrap(), i32::try_from(i).unwrap()));
-                let mut mines = 0;
-                for adj in adjacent {
-                    if self.get(adj).unwrap().is_mine {
-                        mines += 1;
-                    }
-                }
-                self.map[i][j].set_value(mines);
-            }
+    pub fn flag(&mut self, pos: Pos) {
+        if self.check_state() != Stage::Playing {
+            return;
         }
+        let (counts, moves) = (self.counts(), self.moves.len());
+        self.clicks.right += 1;
+        self.moves.push(Move {
+            time: self.stopwatch.elapsed(),
+            click: Click::Flag,
+            pos,
+        });
+        self.board.record();
+        self.board.flag(pos);
+        let change = self.board.take_change();
+        self.remember(change, counts, moves);
     }
 
-    pub fn mine(&mut self, pos: Coords<i32>, prev: &mut [Coords<i32>]) {
-        let tile = &mut self.map[usize::try_from(pos.1).unwrap()][usize::try_from(pos.0).unwrap()];
-        if tile.is_flagged {
-        } else if tile.is_mined {
-            let adjacent = self.get_adjacent_tiles(pos);
-            let (flags, non_flags): (Vec<Coords<i32>>, Vec<Coords<i32>>) = adjacent
-                .iter()
-                .filter(|&&e| !self.get(e).unwrap().is_mined)
-                .partition(|&&e| self.get(e).unwrap().is_flagged);
-            if u8::try_from(flags.len()).unwrap() == self.get(pos).unwrap().value.unwrap() {
-                for t in non_flags {
-                    self.mine(t, &mut []);
-                }
+    /// Works out whether each mine the last click set off was a forced guess
+    /// or a mistake, from the board as it was before the click.
+    fn judge(&mut self, before: &Board) {
+        for pos in self.board.dim.positions() {
+            let was = before.get(pos).unwrap();
+            if was.is_exploded() || !self.board.get(pos).unwrap().is_exploded() {
+                continue;
             }
-        } else if tile.mine() {
-            self.lost = true;
-        } else if tile.value.unwrap() == 0 {
-            let mut adjacent = self.get_adjacent_tiles(pos);
-            adjacent.retain(|e| !prev.contains(e));
-            for adj in adjacent {
-                let mut copy = prev.to_owned();
-                copy.push(adj);
-                self.mine(adj, &mut copy);
+            let Some(verdict) = solver::judge(View::new(before), pos) else {
+                continue;
+            };
+            self.verdict_text.set_text(match verdict {
+                Verdict::ForcedGuess => "Forced guess",
+                Verdict::Mistake => "Logical mistake",
+            });
+            match verdict {
+                Verdict::ForcedGuess => self.forced_guesses += 1,
+                Verdict::Mistake => self.mistakes += 1,
             }
         }
+    }
 
-        let mut mined = 0;
+    fn counts(&self) -> Counts {
+        Counts {
+            clicks: self.clicks,
+            mistakes: self.mistakes,
+            forced_guesses: self.forced_guesses,
+        }
+    }
 
-        for row in &self.map {
-            for tile in row {
-                if tile.is_mined || (tile.is_mine && tile.is_flagged) {
-                    mined += 1;
-                }
+    fn set_counts(&mut self, counts: Counts) {
+        self.clicks = counts.clicks;
+        self.mistakes = counts.mistakes;
+        self.forced_guesses = counts.forced_guesses;
+    }
+
+    /// Keeps what an action changed so it can be undone, along with the
+    /// counts and number of moves from before it, unless the action did not
+    /// change the board.
+    fn remember(&mut self, change: Option<Change>, counts: Counts, moves: usize) {
+        if let Some(change) = change {
+            self.clicks.effective += 1;
+            if self.history.len() == MAX_UNDO {
+                self.history.pop_front();
             }
+            self.history.push_back(Undo {
+                change,
+                counts,
+                moves,
+            });
+            self.future.clear();
+        }
+        self.update_texts();
+    }
+
+    fn update_texts(&mut self) {
+        self.flags_text
+            .set_text(&format!("Flags: {}", self.board.flags));
+        if let Some(text) = &mut self.lives_text {
+            text.set_text(&format!("Lives: {}", self.board.lives_left()));
         }
+    }
+
+    /// The board and every click made on it so far.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            layout: Playfield::layout(self).unwrap(),
+            moves: self.moves.clone(),
+        }
+    }
 
-        self.percentage = mined * 100 / u32::try_from(self.dim.0 * self.dim.1).unwrap();
+    pub fn stats(&self) -> Stats {
+        Stats::new(&self.board, self.clicks, self.stopwatch.elapsed())
     }
 
-    pub fn flag(&mut self, pos: Coords<i32>) {
-        let tile = &mut self.map[usize::try_from(pos.1).unwrap()][usize::try_from(pos.0).unwrap()];
-        if tile.is_mined {
+    /// Takes back the last reveal or flag, along with the clicks, moves and
+    /// judged mines that came with it. Time penalties from lost lives stay
+    /// on the clock.
+    pub fn undo(&mut self) {
+        if self.check_state() != Stage::Playing {
             return;
         }
-        if self.flags > 0 || tile.is_flagged {
-            if let Some(flagged) = tile.flag() {
-                if flagged {
-                    self.flags += 1;
-                } else {
-                    self.flags -= 1;
-                }
-                self.flags_text.set_text(&format!("Flags: {}", self.flags));
-            }
+        if let Some(undo) = self.history.pop_back() {
+            let counts = self.counts();
+            self.set_counts(undo.counts);
+            self.future.push(Redo {
+                change: self.board.apply(undo.change),
+                counts,
+                moves: self.moves.split_off(undo.moves),
+            });
+            self.undone = true;
+            self.update_texts();
         }
     }
 
-    pub fn check_state(&self) -> Stage {
-        if self.lost {
-            return Stage::Lose;
+    pub fn redo(&mut self) {
+        if self.check_state() != Stage::Playing {
+            return;
         }
-
-        for row in &self.map {
-            for tile in row {
-                if !tile.is_mined && !tile.is_mine {
-                    return Stage::Playing;
-                }
-            }
+        if let Some(mut redo) = self.future.pop() {
+            let counts = self.counts();
+            self.set_counts(redo.counts);
+            self.history.push_back(Undo {
+                change: self.board.apply(redo.change),
+                counts,
+                moves: self.moves.len(),
+            });
+            self.moves.append(&mut redo.moves);
+            self.update_texts();
         }
-
-        Stage::Win
     }
 
     pub fn render(
@@ -273,34 +472,324 @@ impl<'a> Map<'a> {
         tex_creator: &'a TextureCreator<WindowContext>,
         status: Option<bool>,
     ) -> Result<(), String> {
-        for (i, row) in self.map.iter().enumerate() {
-            for (j, tile) in row.iter().enumerate() {
-                tile.render(
-                    canvas,
-                    &self.spritesheet,
-                    i32::try_from(i).unwrap() * TILE_SIZE,
-                    i32::try_from(j).unwrap() * TILE_SIZE,
-                    status,
-                )?;
+        for (i, tile) in self.board.tiles().iter().enumerate() {
+            tile.render(
+                canvas,
+                &self.spritesheet,
+                self.board.rules.topology,
+                self.tile_rect(self.board.dim.pos(i)),
+                status,
+            )?;
+        }
+
+        // Dimmed copies of the tiles on the opposite edges, so the player can
+        // see what the tiles along the edge touch.
+        let (dim, rules) = (self.board.dim, self.board.rules);
+        if rules.wrap {
+            let rows = if rules.wraps_rows(dim) {
+                -1..=dim.rows
+            } else {
+                0..=dim.rows - 1
+            };
+            self.spritesheet.set_color_mod(120, 120, 120);
+            for row in rows {
+                for col in -1..=dim.cols {
+                    let ghost = Pos::new(row, col);
+                    if dim.contains(ghost) {
+                        continue;
+                    }
+                    let tile = self.board.get(rules.wrap_pos(dim, ghost).unwrap()).unwrap();
+                    tile.render(
+                        canvas,
+                        &self.spritesheet,
+                        rules.topology,
+                        self.tile_rect(ghost),
+                        status,
+                    )?;
+                }
             }
+            self.spritesheet.set_color_mod(255, 255, 255);
         }
 
         if status.is_none() {
             self.flags_text.render(canvas, font, tex_creator)?;
-            self.time_text
-                .set_text(&format!("Time: {}", self.stopwatch.elapsed().as_secs()));
+            self.time_text.set_text(&match self.stopwatch.remaining() {
+                // Rounded up, so the clock reads 0 only once time is up.
+                Some(left) => format!("Time left: {}", left.as_millis().div_ceil(1000)),
+                None => format!("Time: {}", self.stopwatch.elapsed().as_secs()),
+            });
 
             self.time_text.render(canvas, font, tex_creator)?;
+            if let Some(text) = &mut self.lives_text {
+                text.render(canvas, font, tex_creator)?;
+            }
+            if self.board.safe_death {
+                self.verdict_text.render(canvas, font, tex_creator)?;
+            }
+            if self.board.rules.neighborhood != Neighborhood::Standard {
+                self.render_legend(canvas)?;
+            }
         }
 
         Ok(())
     }
 
-    pub fn inside(&self, x: i32, y: i32) -> Option<Coords<i32>> {
-        if x > self.dim.1 * TILE_SIZE || y > self.dim.0 * TILE_SIZE {
-            None
+    /// A small picture in the top right corner showing which tiles a number
+    /// counts, since it is easy to forget with the unusual neighborhoods.
+    fn render_legend(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        const CELL: i32 = 6;
+        let rules = Rules {
+            wrap: false,
+            ..self.board.rules
+        };
+        let dim = Dim::new(5, 5);
+        let center = Pos::new(2, 2);
+        let (x, y) = (self.width() - 5 * CELL - CELL * 2, CELL);
+
+        canvas.set_draw_color(Color::RGB(28, 28, 28));
+        canvas.fill_rect(Rect::new(
+            x - CELL / 2,
+            y - CELL / 2,
+            u32::try_from(6 * CELL + CELL / 2).unwrap(),
+            u32::try_from(6 * CELL).unwrap(),
+        ))?;
+        let neighbors: Vec<Pos> = rules.neighbors(dim, center).collect();
+        for pos in dim.positions() {
+            canvas.set_draw_color(if pos == center {
+                Color::RGB(230, 230, 230)
+            } else if neighbors.contains(&pos) {
+                Color::RGB(200, 70, 70)
+            } else {
+                Color::RGB(70, 70, 70)
+            });
+            let shift = match rules.topology {
+                Topology::Square => 0,
+                Topology::Hex => pos.row.rem_euclid(2) * CELL / 2,
+            };
+            canvas.fill_rect(Rect::new(
+                x + pos.col * CELL + shift,
+                y + pos.row * CELL,
+                u32::try_from(CELL - 1).unwrap(),
+                u32::try_from(CELL - 1).unwrap(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// The tile under the pixel at (x, y). Clicking a ghost tile acts on the
+    /// tile it mirrors, while walls can not be clicked at all.
+    pub fn inside(&self, x: i32, y: i32) -> Option<Pos> {
+        tile_under(self.board.dim, self.board.rules, x, y)
+            .filter(|&pos| !self.board.get(pos).unwrap().is_wall())
+    }
+}
+
+/// The top left pixel of `pos` on a `dim` board. Positions just outside of
+/// the board are the ghost tiles.
+fn tile_corner(dim: Dim, rules: Rules, pos: Pos) -> (i32, i32) {
+    let (margin_x, margin_y) = Map::margin(dim, rules);
+    match rules.topology {
+        Topology::Square => (
+            margin_x + pos.col * TILE_SIZE,
+            margin_y + pos.row * TILE_SIZE,
+        ),
+        Topology::Hex => (
+            margin_x + pos.col * TILE_SIZE + pos.row.rem_euclid(2) * TILE_SIZE / 2,
+            margin_y + pos.row * HEX_ROW_STEP,
+        ),
+    }
+}
+
+/// The tile under the pixel at (x, y) of a `dim` board, with ghost tiles
+/// mapped onto the tiles they mirror.
+fn tile_under(dim: Dim, rules: Rules, x: i32, y: i32) -> Option<Pos> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    let (margin_x, margin_y) = Map::margin(dim, rules);
+    let (bx, by) = (x - margin_x, y - margin_y);
+    let pos = match rules.topology {
+        Topology::Square => Pos::new(by.div_euclid(TILE_SIZE), bx.div_euclid(TILE_SIZE)),
+        Topology::Hex => {
+            // The pointed top of a row overlaps the row above it, so check
+            // the lower row first and fall back to the upper one.
+            let row = by.div_euclid(HEX_ROW_STEP);
+            [row, row - 1].into_iter().find_map(|row| {
+                let shifted = bx - row.rem_euclid(2) * TILE_SIZE / 2;
+                let pos = Pos::new(row, shifted.div_euclid(TILE_SIZE));
+                let (left, top) = tile_corner(dim, rules, pos);
+                let dx = f64::from(x - left) + 0.5 - f64::from(TILE_SIZE) / 2.0;
+                let dy = f64::from(y - top) + 0.5 - f64::from(HEX_HEIGHT) / 2.0;
+                let slope = f64::from(HEX_HEIGHT) / 4.0 / (f64::from(TILE_SIZE) / 2.0);
+                (dx.abs() <= f64::from(TILE_SIZE) / 2.0
+                    && dy.abs() <= f64::from(HEX_HEIGHT) / 2.0 - dx.abs() * slope)
+                    .then_some(pos)
+            })?
+        }
+    };
+    rules.wrap_pos(dim, pos)
+}
+
+impl<'a> Playfield<'a> for Map<'a> {
+    fn width(&self) -> i32 {
+        Map::width(self)
+    }
+
+    fn height(&self) -> i32 {
+        Map::height(self)
+    }
+
+    fn render(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        font: &Font,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        status: Option<bool>,
+    ) -> Result<(), String> {
+        Map::render(self, canvas, font, tex_creator, status)
+    }
+
+    fn summary(&self) -> String {
+        let summary = format!(
+            "Time: {}s | Progress: {}%",
+            seconds(self.stopwatch.elapsed()),
+            self.board.percentage()
+        );
+        let lives = self.board.rules.lives;
+        let summary = if lives > 1 {
+            format!(
+                "{summary} | Lives used: {}/{lives}",
+                self.board.exploded.min(u32::from(lives))
+            )
+        } else {
+            summary
+        };
+        if self.undone {
+            format!("{summary} | Unranked")
+        } else {
+            summary
+        }
+    }
+
+    fn name(&self) -> String {
+        if let Some(name) = &self.layout {
+            name.clone()
+        } else if self.daily.is_some() {
+            "Daily".to_string()
+        } else {
+            self.size.to_string()
+        }
+    }
+
+    fn stats(&self) -> Option<Stats> {
+        Some(Map::stats(self))
+    }
+
+    fn layout(&self) -> Option<Layout> {
+        let name = match (&self.layout, self.daily) {
+            (Some(name), _) => name.clone(),
+            (None, Some(date)) => format!("daily-{date}"),
+            (None, None) => format!("{}-{}", self.size.to_string().to_lowercase(), self.seed),
+        };
+        Some(Layout::of(&name, &self.board))
+    }
+
+    fn analysis(&self) -> Option<String> {
+        self.board.safe_death.then(|| {
+            if self.board.rules.multi_mine {
+                return "Mistakes aren't judged with multi-mine rules".to_string();
+            }
+            format!(
+                "Mistakes: {} | Forced guesses: {}",
+                self.mistakes, self.forced_guesses
+            )
+        })
+    }
+
+    fn share(&self) -> Option<String> {
+        let date = self.daily?;
+        let time = self.stopwatch.elapsed();
+        let result = if self.board.check_state() == Stage::Win {
+            format!("Cleared in {}s", seconds(time))
         } else {
-            Some((y / TILE_SIZE, x / TILE_SIZE))
+            format!(
+                "Blew up after {}s at {}%",
+                seconds(time),
+                self.board.percentage()
+            )
+        };
+        Some(format!(
+            "Minesweeper daily {date}\n{result}\n3BV: {} | Clicks: {}\n",
+            self.board.three_bv().0,
+            self.clicks.total()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T: i32 = TILE_SIZE;
+
+    fn wrapped() -> Rules {
+        Rules {
+            wrap: true,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn finds_the_tile_under_square_edges() {
+        let (dim, rules) = (Dim::new(9, 9), Rules::default());
+        assert_eq!(tile_under(dim, rules, 0, 0), Some(Pos::new(0, 0)));
+        assert_eq!(tile_under(dim, rules, T - 1, T), Some(Pos::new(1, 0)));
+        assert_eq!(
+            tile_under(dim, rules, 9 * T - 1, 9 * T - 1),
+            Some(Pos::new(8, 8))
+        );
+        assert_eq!(tile_under(dim, rules, 9 * T, 0), None);
+        assert_eq!(tile_under(dim, rules, 0, 9 * T), None);
+        assert_eq!(tile_under(dim, rules, -1, 0), None);
+        assert_eq!(tile_under(dim, rules, 0, -1), None);
+        assert_eq!(Map::pixel_size(dim, rules), (9 * T, 9 * T));
+    }
+
+    #[test]
+    fn ghost_tiles_mirror_the_far_edge() {
+        let (dim, rules) = (Dim::new(4, 6), wrapped());
+        assert_eq!(Map::pixel_size(dim, rules), (8 * T, 6 * T));
+        assert_eq!(tile_under(dim, rules, 0, 0), Some(Pos::new(3, 5)));
+        assert_eq!(tile_under(dim, rules, T, T), Some(Pos::new(0, 0)));
+        let (right, bottom) = Map::pixel_size(dim, rules);
+        assert_eq!(
+            tile_under(dim, rules, right - 1, bottom - 1),
+            Some(Pos::new(0, 0))
+        );
+        assert_eq!(
+            tile_under(dim, rules, right - T - 1, bottom - T - 1),
+            Some(Pos::new(3, 5))
+        );
+    }
+
+    #[test]
+    fn finds_the_hex_under_the_centre_of_every_tile() {
+        let rules = Rules {
+            topology: Topology::Hex,
+            ..Rules::default()
+        };
+        let dim = Dim::new(5, 4);
+        for pos in dim.positions() {
+            let (x, y) = tile_corner(dim, rules, pos);
+            let centre = (x + T / 2, y + HEX_HEIGHT / 2);
+            assert_eq!(tile_under(dim, rules, centre.0, centre.1), Some(pos));
         }
+        // Left of the shifted odd rows there is nothing.
+        assert_eq!(
+            tile_under(dim, rules, 2, HEX_ROW_STEP + HEX_HEIGHT / 2),
+            None
+        );
+        let (right, bottom) = Map::pixel_size(dim, rules);
+        assert_eq!(tile_under(dim, rules, right - 1, bottom - 1), None);
     }
 }
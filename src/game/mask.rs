@@ -0,0 +1,220 @@
+use std::{fs, path::Path};
+
+use resource::resource;
+use sdl2::{image::LoadSurface, pixels::PixelFormatEnum, surface::Surface};
+
+use super::{
+    board::Size,
+    layout::{checked_dim, MAX_CELLS},
+    Dim, Pos,
+};
+
+/// Room for the mines as well as the tiles kept free by the first click.
+const MIN_TILES: u32 = 32;
+
+/// The shape of an irregular board. Tiles outside of it are walls: they are
+/// not drawn, never hold a mine and are not anyone's neighbor.
+#[derive(Clone)]
+pub struct Mask {
+    pub name: String,
+    pub dim: Dim,
+    // Row-major, `true` for tiles that can be played on.
+    open: Vec<bool>,
+}
+
+impl Mask {
+    /// Builds a mask from rows of tiles, cutting off empty rows and columns
+    /// around the shape.
+    fn from_rows(name: &str, rows: &[Vec<bool>]) -> Result<Mask, String> {
+        let used = |row: &Vec<bool>| row.contains(&true);
+        let top = rows.iter().position(used);
+        let bottom = rows.iter().rposition(used);
+        let (Some(top), Some(bottom)) = (top, bottom) else {
+            return Err(format!("Mask \"{name}\" is empty"));
+        };
+        let rows = &rows[top..=bottom];
+        let left = rows
+            .iter()
+            .filter_map(|row| row.iter().position(|&e| e))
+            .min()
+            .unwrap();
+        let right = rows
+            .iter()
+            .filter_map(|row| row.iter().rposition(|&e| e))
+            .max()
+            .unwrap();
+
+        let (height, width) = (rows.len(), right - left + 1);
+        // Images can be far larger than any board, and masked boards are
+        // shared as codes just like the others.
+        let dim = i32::try_from(height)
+            .ok()
+            .zip(i32::try_from(width).ok())
+            .and_then(|(rows, cols)| checked_dim(rows, cols))
+            .ok_or_else(|| {
+                format!(
+                    "Mask \"{name}\" can't be {width}x{height}, it can have at most {MAX_CELLS} \
+                     tiles and {} on a side",
+                    u16::MAX
+                )
+            })?;
+        let open: Vec<bool> = rows
+            .iter()
+            .flat_map(|row| (left..=right).map(|col| row.get(col).copied().unwrap_or(false)))
+            .collect();
+        let mask = Mask {
+            name: name.to_string(),
+            dim,
+            open,
+        };
+        if mask.cells() < MIN_TILES {
+            return Err(format!(
+                "Mask \"{name}\" needs at least {MIN_TILES} playable tiles"
+            ));
+        }
+        Ok(mask)
+    }
+
+    /// Reads a mask drawn with `#` for playable tiles and `.` or spaces for
+    /// walls, one line per row.
+    pub fn parse(name: &str, text: &str) -> Result<Mask, String> {
+        let rows = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                line.trim_end()
+                    .chars()
+                    .map(|c| match c {
+                        '#' => Ok(true),
+                        '.' | ' ' => Ok(false),
+                        _ => Err(format!(
+                            "Unexpected '{c}' on line {} of mask \"{name}\"",
+                            i + 1
+                        )),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<bool>>, String>>()?;
+        Mask::from_rows(name, &rows)
+    }
+
+    /// Loads a mask from a text file or a PNG image. Every pixel of an image
+    /// is a tile, and the opaque pixels that are not white can be played on.
+    pub fn load(path: &Path) -> Result<Mask, String> {
+        let name = path.file_stem().map_or_else(
+            || path.display().to_string(),
+            |e| e.to_string_lossy().into_owned(),
+        );
+        let is_png = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+        if !is_png {
+            let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+            return Mask::parse(&name, &text);
+        }
+
+        let surface = Surface::from_file(path)?.convert_format(PixelFormatEnum::RGBA32)?;
+        let width = usize::try_from(surface.width()).unwrap();
+        let height = usize::try_from(surface.height()).unwrap();
+        let pitch = usize::try_from(surface.pitch()).unwrap();
+        let rows = surface.with_lock(|pixels| {
+            (0..height)
+                .map(|y| {
+                    (0..width)
+                        .map(|x| {
+                            let px = &pixels[y * pitch + x * 4..][..4];
+                            px[3] >= 128 && px[..3].iter().any(|&e| e < 200)
+                        })
+                        .collect()
+                })
+                .collect::<Vec<Vec<bool>>>()
+        });
+        Mask::from_rows(&name, &rows)
+    }
+
+    /// The shapes that come with the game.
+    pub fn builtin() -> Vec<Mask> {
+        [
+            ("Heart", resource!("res/masks/heart.txt")),
+            ("Donut", resource!("res/masks/donut.txt")),
+            ("Diamond", resource!("res/masks/diamond.txt")),
+            ("Cross", resource!("res/masks/cross.txt")),
+        ]
+        .iter()
+        .map(|(name, text)| {
+            Mask::parse(name, std::str::from_utf8(text).unwrap()).expect("Invalid built-in mask")
+        })
+        .collect()
+    }
+
+    /// The built-in shapes followed by every mask found in the `masks`
+    /// directory, and why each file that could not be read was skipped.
+    pub fn available() -> (Vec<Mask>, Vec<String>) {
+        let mut masks = Mask::builtin();
+        let mut skipped = vec![];
+        let Ok(entries) = fs::read_dir("masks") else {
+            return (masks, skipped);
+        };
+        let mut paths: Vec<_> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+        paths.sort();
+        for path in paths {
+            match Mask::load(&path) {
+                Ok(mask) => masks.push(mask),
+                Err(e) => skipped.push(format!("{}: {e}", path.display())),
+            }
+        }
+        (masks, skipped)
+    }
+
+    pub fn is_open(&self, pos: Pos) -> bool {
+        self.dim.index(pos).is_some_and(|i| self.open[i])
+    }
+
+    /// The number of playable tiles.
+    pub fn cells(&self) -> u32 {
+        u32::try_from(self.open.iter().filter(|&&e| e).count()).unwrap()
+    }
+
+    /// As many mines as `size` would have on the same number of tiles.
+    pub fn mines(&self, size: Size) -> u32 {
+        let total = u32::try_from(size.dim().cells()).unwrap();
+        ((self.cells() * size.mines() + total / 2) / total).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trims_the_empty_space_around_the_shape() {
+        let text = "\n  ......\n  ############  \n  .############\n\n  ############\n   ...\n";
+        let mask = Mask::parse("test", text).unwrap();
+        assert_eq!(mask.dim, Dim::new(4, 13));
+        assert_eq!(mask.cells(), 36);
+        assert!(mask.is_open(Pos::new(0, 0)));
+        assert!(!mask.is_open(Pos::new(0, 12)));
+        assert!(!mask.is_open(Pos::new(1, 0)));
+        assert!(mask.is_open(Pos::new(1, 12)));
+        assert!(!mask.is_open(Pos::new(2, 5)));
+        assert!(mask.is_open(Pos::new(3, 11)));
+    }
+
+    #[test]
+    fn rejects_bad_masks() {
+        assert!(Mask::parse("test", "..\n  \n").is_err());
+        assert!(Mask::parse("test", "##x#\n").is_err());
+        let small = "#".repeat(usize::try_from(MIN_TILES).unwrap() - 1);
+        assert!(Mask::parse("test", &small).is_err());
+        assert!(Mask::parse("test", &format!("{small}#")).is_ok());
+    }
+
+    #[test]
+    fn rejects_masks_too_big_for_a_board() {
+        let wide = vec![vec![true; usize::from(u16::MAX) + 1]];
+        assert!(Mask::from_rows("wide", &wide).is_err());
+        assert!(Mask::from_rows("wide", &[vec![true; usize::from(u16::MAX)]]).is_ok());
+        let big = vec![vec![true; 2048]; MAX_CELLS / 2048 + 1];
+        assert!(Mask::from_rows("big", &big).is_err());
+    }
+}
@@ -1,11 +1,117 @@
+pub mod board;
+pub mod endless;
+pub mod layout;
 pub mod map;
+pub mod mask;
+pub mod puzzle;
+pub mod replay;
+pub mod rules;
+pub mod stats;
 pub mod tile;
-
-pub type Coords<T> = (T, T);
+pub mod time_attack;
+pub mod world;
 
 #[derive(PartialEq, Eq)]
 pub enum Stage {
     Lose,
+    /// Lost because a countdown ran out.
+    TimeOut,
     Playing,
     Win,
 }
+
+/// A tile on the board. Positions outside of the board can be represented, but
+/// are never handed out by `Dim`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Pos {
+    pub row: i32,
+    pub col: i32,
+}
+
+impl Pos {
+    pub fn new(row: i32, col: i32) -> Pos {
+        Pos { row, col }
+    }
+}
+
+/// The size of a board in tiles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Dim {
+    pub rows: i32,
+    pub cols: i32,
+}
+
+impl Dim {
+    pub fn new(rows: i32, cols: i32) -> Dim {
+        Dim { rows, cols }
+    }
+
+    pub fn cells(self) -> usize {
+        usize::try_from(self.rows * self.cols).unwrap()
+    }
+
+    pub fn contains(self, pos: Pos) -> bool {
+        (0..self.rows).contains(&pos.row) && (0..self.cols).contains(&pos.col)
+    }
+
+    /// The row-major index of `pos`, or `None` if it lies outside of the board.
+    pub fn index(self, pos: Pos) -> Option<usize> {
+        self.contains(pos)
+            .then(|| usize::try_from(pos.row * self.cols + pos.col).unwrap())
+    }
+
+    pub fn pos(self, index: usize) -> Pos {
+        let index = i32::try_from(index).unwrap();
+        Pos::new(index / self.cols, index % self.cols)
+    }
+
+    pub fn positions(self) -> impl Iterator<Item = Pos> {
+        (0..self.rows).flat_map(move |row| (0..self.cols).map(move |col| Pos::new(row, col)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rules::Rules, *};
+
+    #[test]
+    fn contains_up_to_the_last_row_and_column() {
+        let dim = Dim::new(3, 5);
+        assert!(dim.contains(Pos::new(0, 0)));
+        assert!(dim.contains(Pos::new(2, 4)));
+        assert!(!dim.contains(Pos::new(3, 4)));
+        assert!(!dim.contains(Pos::new(2, 5)));
+        assert!(!dim.contains(Pos::new(-1, 0)));
+        assert!(!dim.contains(Pos::new(0, -1)));
+        assert_eq!(dim.index(Pos::new(2, 4)), Some(14));
+        assert_eq!(dim.index(Pos::new(1, 5)), None);
+        assert_eq!(dim.index(Pos::new(-1, 4)), None);
+    }
+
+    #[test]
+    fn indices_round_trip() {
+        let dim = Dim::new(7, 4);
+        let positions: Vec<Pos> = dim.positions().collect();
+        assert_eq!(positions.len(), dim.cells());
+        for (i, &pos) in positions.iter().enumerate() {
+            assert_eq!(dim.index(pos), Some(i));
+            assert_eq!(dim.pos(i), pos);
+        }
+    }
+
+    #[test]
+    fn wraps_onto_the_far_edge() {
+        let dim = Dim::new(3, 5);
+        let wrap = Rules {
+            wrap: true,
+            ..Rules::default()
+        };
+        assert_eq!(wrap.wrap_pos(dim, Pos::new(-1, -1)), Some(Pos::new(2, 4)));
+        assert_eq!(wrap.wrap_pos(dim, Pos::new(3, 5)), Some(Pos::new(0, 0)));
+        assert_eq!(wrap.wrap_pos(dim, Pos::new(2, 4)), Some(Pos::new(2, 4)));
+        let flat = Rules::default();
+        assert_eq!(flat.wrap_pos(dim, Pos::new(2, 4)), Some(Pos::new(2, 4)));
+        assert_eq!(flat.wrap_pos(dim, Pos::new(3, 0)), None);
+        assert_eq!(flat.wrap_pos(dim, Pos::new(0, -1)), None);
+    }
+}
@@ -0,0 +1,262 @@
+use std::{collections::HashSet, fs, path::Path};
+
+use resource::resource;
+
+use crate::agent::solver;
+
+use super::{board::Board, layout::Layout, rules::Rules, Pos, Stage};
+
+/// Where the puzzles that were solved are kept between runs.
+pub const PROGRESS_PATH: &str = "puzzles.txt";
+
+/// A board with some tiles already revealed or flagged, which can be cleared
+/// by logic alone.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    /// The pack the puzzle comes from.
+    pub pack: String,
+    pub layout: Layout,
+    revealed: Vec<Pos>,
+    flagged: Vec<Pos>,
+}
+
+impl Puzzle {
+    /// Reads one puzzle in the layout format, where `O` is a revealed tile
+    /// and `F` a flagged mine.
+    fn parse(pack: &str, name: &str, text: &str) -> Result<Puzzle, String> {
+        let mut revealed = vec![];
+        let mut flagged = vec![];
+        let mut plain = String::new();
+        // The first line holds the size, so the board starts on the second.
+        for (line, row) in text.lines().filter(|e| !e.trim().is_empty()).zip(-1..) {
+            for (c, col) in line.trim().chars().zip(0..) {
+                match c {
+                    'O' => revealed.push(Pos::new(row, col)),
+                    'F' => flagged.push(Pos::new(row, col)),
+                    _ => (),
+                }
+            }
+            plain.extend(line.trim().chars().map(|c| match c {
+                'O' => '.',
+                'F' => '*',
+                c => c,
+            }));
+            plain.push('\n');
+        }
+        let mut layout = Layout::parse(name, &plain)?;
+        layout.rules = Some(Rules::default());
+        let puzzle = Puzzle {
+            pack: pack.to_string(),
+            layout,
+            revealed,
+            flagged,
+        };
+        puzzle.check()?;
+        Ok(puzzle)
+    }
+
+    /// The board as the player gets it, with the given tiles revealed and
+    /// flagged.
+    pub fn board(&self) -> Board {
+        let mut board = self.layout.board(Rules::default());
+        for &pos in &self.revealed {
+            board.mine(pos);
+        }
+        for &pos in &self.flagged {
+            board.flag(pos);
+        }
+        board
+    }
+
+    /// Makes sure the puzzle is fair: nothing revealed is a mine, and the
+    /// solver clears it without guessing, so there is only one solution.
+    fn check(&self) -> Result<(), String> {
+        let name = &self.layout.name;
+        if self.revealed.iter().any(|&e| self.layout.get(e) != Some(0)) {
+            return Err(format!("Puzzle \"{name}\" reveals a mine or a wall"));
+        }
+        if self.flagged.iter().any(|&e| self.layout.get(e) != Some(1)) {
+            return Err(format!("Puzzle \"{name}\" flags a tile without a mine"));
+        }
+        let mut board = self.board();
+        if board.rules.multi_mine {
+            return Err(format!(
+                "Puzzle \"{name}\" has tiles with several mines, which can't be checked"
+            ));
+        }
+        if board.check_state() != Stage::Playing {
+            return Err(format!("Puzzle \"{name}\" is solved from the start"));
+        }
+        if !solver::solve(&mut board) {
+            return Err(format!(
+                "Puzzle \"{name}\" can not be solved without guessing"
+            ));
+        }
+        Ok(())
+    }
+
+    /// What the puzzle is stored as once it was solved.
+    fn id(&self) -> String {
+        format!("{}/{}", self.pack, self.layout.name)
+    }
+}
+
+/// A named set of puzzles from one file. Every puzzle starts with its name in
+/// brackets on a line of its own, followed by the board:
+///
+/// ```text
+/// [Corner]
+/// 4x3
+/// OO.*
+/// OO..
+/// ..F.
+/// ```
+pub struct Pack {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl Pack {
+    /// Reads a pack and checks every puzzle in it. Puzzles that fail the
+    /// check are left out, and the reasons returned next to the pack.
+    pub fn parse(name: &str, text: &str) -> (Pack, Vec<String>) {
+        let mut pack = Pack {
+            name: name.to_string(),
+            puzzles: vec![],
+        };
+        let mut errors = vec![];
+        let mut sections = vec![];
+        for line in text.lines() {
+            let line = line.trim();
+            if let Some(title) = line.strip_prefix('[').and_then(|e| e.strip_suffix(']')) {
+                sections.push((title, String::new()));
+            } else if let Some((_, board)) = sections.last_mut() {
+                board.push_str(line);
+                board.push('\n');
+            }
+        }
+        for (title, board) in sections {
+            match Puzzle::parse(name, title, &board) {
+                Ok(puzzle) => pack.puzzles.push(puzzle),
+                Err(e) => errors.push(e),
+            }
+        }
+        (pack, errors)
+    }
+
+    pub fn load(path: &Path) -> Result<(Pack, Vec<String>), String> {
+        let name = path.file_stem().map_or_else(
+            || path.display().to_string(),
+            |e| e.to_string_lossy().into_owned(),
+        );
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Ok(Pack::parse(&name, &text))
+    }
+
+    /// The packs that come with the game, each with the puzzles that failed
+    /// the check.
+    pub fn builtin() -> Vec<(Pack, Vec<String>)> {
+        [("Starter", resource!("res/puzzles/starter.txt"))]
+            .iter()
+            .map(|(name, text)| Pack::parse(name, &String::from_utf8_lossy(text)))
+            .collect()
+    }
+
+    /// The built-in packs followed by every pack found in the `puzzles`
+    /// directory, and why the files and puzzles that were skipped could not
+    /// be used.
+    pub fn available() -> (Vec<Pack>, Vec<String>) {
+        let mut loaded: Vec<_> = Pack::builtin().into_iter().map(Ok).collect();
+        if let Ok(entries) = fs::read_dir("puzzles") {
+            let mut paths: Vec<_> = entries.filter_map(|e| Some(e.ok()?.path())).collect();
+            paths.sort();
+            loaded.extend(
+                paths
+                    .iter()
+                    .map(|path| Pack::load(path).map_err(|e| format!("{}: {e}", path.display()))),
+            );
+        }
+        let mut packs = vec![];
+        let mut skipped = vec![];
+        for e in loaded {
+            match e {
+                Ok((pack, errors)) => {
+                    skipped.extend(errors.into_iter().map(|e| format!("{}: {e}", pack.name)));
+                    if !pack.puzzles.is_empty() {
+                        packs.push(pack);
+                    }
+                }
+                Err(e) => skipped.push(e),
+            }
+        }
+        (packs, skipped)
+    }
+}
+
+/// The puzzles that were solved, one `<pack>/<puzzle>` per line.
+#[derive(Default)]
+pub struct Progress {
+    solved: HashSet<String>,
+}
+
+impl Progress {
+    pub fn load() -> Progress {
+        let text = fs::read_to_string(PROGRESS_PATH).unwrap_or_default();
+        Progress {
+            solved: text.lines().map(str::to_string).collect(),
+        }
+    }
+
+    pub fn is_solved(&self, puzzle: &Puzzle) -> bool {
+        self.solved.contains(&puzzle.id())
+    }
+
+    /// Marks `puzzle` as solved and saves the progress.
+    pub fn solve(&mut self, puzzle: &Puzzle) -> Result<(), String> {
+        if !self.solved.insert(puzzle.id()) {
+            return Ok(());
+        }
+        let mut lines: Vec<&str> = self.solved.iter().map(String::as_str).collect();
+        lines.sort_unstable();
+        fs::write(PROGRESS_PATH, lines.join("\n") + "\n").map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_puzzles_pass_the_check() {
+        for (pack, errors) in Pack::builtin() {
+            assert!(errors.is_empty(), "{}: {errors:?}", pack.name);
+            assert!(!pack.puzzles.is_empty());
+        }
+    }
+
+    #[test]
+    fn reads_revealed_and_flagged_tiles() {
+        let (pack, errors) = Pack::parse("test", "[Corner]\n4x3\nOO.*\nOO..\n..F.\n");
+        assert!(errors.is_empty(), "{errors:?}");
+        let puzzle = &pack.puzzles[0];
+        assert_eq!(puzzle.layout.name, "Corner");
+        assert_eq!(puzzle.id(), "test/Corner");
+        let board = puzzle.board();
+        assert!(board.get(Pos::new(0, 0)).unwrap().is_mined());
+        assert!(board.get(Pos::new(2, 2)).unwrap().is_flagged());
+        assert!(!board.get(Pos::new(0, 3)).unwrap().is_mined());
+    }
+
+    #[test]
+    fn skips_unfair_puzzles() {
+        let text = "[Empty]\n\
+                    [Flag]\n3x1\nF.*\n\
+                    [Guess]\n3x2\nO.*\n...\n\
+                    [Stack]\n3x1\nO.2\n\
+                    [Done]\n2x1\nO*\n\
+                    [Broken]\n9x9\n";
+        let (pack, errors) = Pack::parse("test", text);
+        assert!(pack.puzzles.is_empty());
+        assert_eq!(errors.len(), 6, "{errors:?}");
+    }
+}
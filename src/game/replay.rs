@@ -0,0 +1,386 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::date::Date;
+
+use super::{
+    layout::Layout,
+    rules::{Neighborhood, Rules, Topology},
+    stats::seconds,
+    Pos,
+};
+
+/// Where the replays of finished games are saved.
+pub const REPLAYS_DIR: &str = "replays";
+/// How many pixels a tile is wide in the coordinates of RAW replays.
+const RAW_TILE: i32 = 16;
+
+/// What a click did, as far as the game is concerned.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Click {
+    Reveal,
+    Flag,
+    /// A reveal on a number, which opens the tiles around it.
+    Chord,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Move {
+    /// How long after the start of the game the click was made.
+    pub time: Duration,
+    pub click: Click,
+    pub pos: Pos,
+}
+
+/// Every move of a game on a fixed board, so it can be watched again.
+///
+/// Replays are kept in the RAW format of the competitive community, the text
+/// format that Arbiter, Viennasweeper and the other clones' videos are
+/// converted to and from. Their own binary files are not read, and have to
+/// be converted to RAW first. Only classic games can be written: square
+/// tiles, no walls, wrapping or unusual numbers, a single mine per tile and a
+/// single life.
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub layout: Layout,
+    pub moves: Vec<Move>,
+}
+
+impl Replay {
+    /// Whether the game can be written as a RAW replay, which has no room
+    /// for anything but the classic rules.
+    pub fn is_classic(&self) -> bool {
+        let rules = self.layout.rules.unwrap_or_default();
+        rules.topology == Topology::Square
+            && rules.neighborhood == Neighborhood::Standard
+            && !rules.wrap
+            && rules.lives == 1
+            && self
+                .layout
+                .dim
+                .positions()
+                .all(|pos| self.layout.get(pos).is_some_and(|e| e <= 1))
+    }
+
+    /// Writes the replay in the RAW format. Every click is a press and a
+    /// release on the same tile, with chords as middle clicks.
+    pub fn to_raw(&self, date: Date) -> Result<String, String> {
+        if !self.is_classic() {
+            return Err("Only classic games can be saved as RAW replays".to_string());
+        }
+        let dim = self.layout.dim;
+        let mines = self.layout.mines();
+        let level = match (dim.cols, dim.rows, mines) {
+            (9, 9, 10) => "Beginner",
+            (16, 16, 40) => "Intermediate",
+            (30, 16, 99) => "Expert",
+            _ => "Custom",
+        };
+        let mut out = String::new();
+        writeln!(out, "RawVF_Version: Rev5").unwrap();
+        writeln!(out, "Program: minesweeper").unwrap();
+        writeln!(out, "Version: {}", env!("CARGO_PKG_VERSION")).unwrap();
+        writeln!(out, "Timestamp: {date}").unwrap();
+        writeln!(out, "Level: {level}").unwrap();
+        writeln!(out, "Width: {}", dim.cols).unwrap();
+        writeln!(out, "Height: {}", dim.rows).unwrap();
+        writeln!(out, "Mines: {mines}").unwrap();
+        writeln!(out, "Marks: Off").unwrap();
+        writeln!(out, "Board:").unwrap();
+        for row in 0..dim.rows {
+            for col in 0..dim.cols {
+                let mine = self.layout.get(Pos::new(row, col)) == Some(1);
+                out.push(if mine { '*' } else { '0' });
+            }
+            out.push('\n');
+        }
+        writeln!(out, "Events:").unwrap();
+        writeln!(out, "0.000 start").unwrap();
+        for e in &self.moves {
+            let (press, release) = match e.click {
+                Click::Reveal => ("lc", "lr"),
+                Click::Flag => ("rc", "rr"),
+                Click::Chord => ("mc", "mr"),
+            };
+            let (x, y) = (e.pos.col, e.pos.row);
+            let at = format!(
+                "{} {} ({} {})",
+                x + 1,
+                y + 1,
+                x * RAW_TILE + RAW_TILE / 2,
+                y * RAW_TILE + RAW_TILE / 2
+            );
+            let time = seconds(e.time);
+            writeln!(out, "{time} {press} {at}").unwrap();
+            writeln!(out, "{time} {release} {at}").unwrap();
+        }
+        Ok(out)
+    }
+
+    /// Reads a RAW replay. Presses and releases are turned back into clicks:
+    /// a left release reveals, a right press flags, and a middle release or
+    /// letting go of both buttons chords. Mouse moves and clicks off the
+    /// board are skipped.
+    pub fn parse_raw(name: &str, text: &str) -> Result<Replay, String> {
+        let mut lines = text.lines().map(str::trim);
+        let mut header = vec![];
+        for line in lines.by_ref() {
+            if line.eq_ignore_ascii_case("Board:") {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                header.push((key.trim().to_lowercase(), value.trim()));
+            }
+        }
+        let field = |key: &str| {
+            header
+                .iter()
+                .find(|e| e.0 == key)
+                .and_then(|e| e.1.parse::<i32>().ok())
+                .ok_or_else(|| format!("Replay \"{name}\" has no {key}"))
+        };
+        let (width, height) = (field("width")?, field("height")?);
+
+        // The board becomes a layout, which checks its size on the way.
+        let mut board = format!("{width}x{height}\n");
+        for line in lines.by_ref().take(usize::try_from(height).unwrap_or(0)) {
+            board.extend(line.chars().map(|c| if c == '*' { '*' } else { '.' }));
+            board.push('\n');
+        }
+        let mut layout = Layout::parse(name, &board)?;
+        layout.rules = Some(Rules::default());
+
+        if !lines.by_ref().any(|e| e.eq_ignore_ascii_case("Events:")) {
+            return Err(format!("Replay \"{name}\" has no events"));
+        }
+        let mut moves = vec![];
+        let (mut left, mut right) = (false, false);
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let [time, event, ..] = words[..] else {
+                continue;
+            };
+            let Ok(time) = time.parse::<f64>() else {
+                continue;
+            };
+            let time = Duration::from_secs_f64(time.max(0.0));
+            let click = match event {
+                "lc" => {
+                    left = true;
+                    None
+                }
+                "rc" => {
+                    right = true;
+                    (!left).then_some(Click::Flag)
+                }
+                // Letting go of one button of a chord is the chord, and the
+                // other button is then let go of for nothing.
+                "lr" => {
+                    let pressed = left;
+                    left = false;
+                    pressed.then_some(if right { Click::Chord } else { Click::Reveal })
+                }
+                "rr" => {
+                    right = false;
+                    let chord = left;
+                    left = false;
+                    chord.then_some(Click::Chord)
+                }
+                "mr" => Some(Click::Chord),
+                _ => None,
+            };
+            if let Some(pos) = click.and_then(|_| raw_pos(&words[2..])) {
+                if layout.dim.contains(pos) {
+                    moves.push(Move {
+                        time,
+                        click: click.unwrap(),
+                        pos,
+                    });
+                }
+            }
+        }
+        Ok(Replay { layout, moves })
+    }
+
+    /// Whether `path` looks like a replay, RAW or not, rather than a board.
+    pub fn is_replay(path: &Path) -> bool {
+        path.extension().is_some_and(|e| {
+            ["raw", "avf", "rmv"]
+                .iter()
+                .any(|ext| e.eq_ignore_ascii_case(ext))
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let name = path.file_stem().map_or_else(
+            || path.display().to_string(),
+            |e| e.to_string_lossy().into_owned(),
+        );
+        if !path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("raw"))
+        {
+            return Err(format!(
+                "Replay \"{name}\" is an Arbiter or Viennasweeper file, convert it to RAW first"
+            ));
+        }
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Replay::parse_raw(&name, &text)
+    }
+
+    /// Writes the replay to `replays/<name>-<date>.raw`, with a number added
+    /// if a replay of the same board was already saved that day. Returns the
+    /// path.
+    pub fn save(&self, date: Date) -> Result<PathBuf, String> {
+        let raw = self.to_raw(date)?;
+        fs::create_dir_all(REPLAYS_DIR).map_err(|e| e.to_string())?;
+        let path = free_path(
+            Path::new(REPLAYS_DIR),
+            &format!("{}-{date}", self.layout.name),
+        );
+        fs::write(&path, raw).map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+/// The first of `<name>.raw`, `<name>-2.raw`, ... that is not taken in `dir`.
+fn free_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(format!("{name}.raw"));
+    let mut i = 1;
+    while path.exists() {
+        i += 1;
+        path = dir.join(format!("{name}-{i}.raw"));
+    }
+    path
+}
+
+/// The tile of a RAW mouse event. The pixel position in brackets is used when
+/// it is there, as not every program writes the tile.
+fn raw_pos(words: &[&str]) -> Option<Pos> {
+    let number = |e: &str| e.trim_matches(|c| c == '(' || c == ')').parse::<i32>().ok();
+    match words {
+        [.., px, py] if px.starts_with('(') => Some(Pos::new(
+            number(py)?.div_euclid(RAW_TILE),
+            number(px)?.div_euclid(RAW_TILE),
+        )),
+        [x, y, ..] => Some(Pos::new(number(y)? - 1, number(x)? - 1)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(text: &str, rules: Rules) -> Replay {
+        let mut layout = Layout::parse("test", text).unwrap();
+        layout.rules = Some(rules);
+        Replay {
+            layout,
+            moves: vec![
+                Move {
+                    time: Duration::from_millis(250),
+                    click: Click::Reveal,
+                    pos: Pos::new(2, 0),
+                },
+                Move {
+                    time: Duration::from_millis(1_500),
+                    click: Click::Flag,
+                    pos: Pos::new(0, 2),
+                },
+                Move {
+                    time: Duration::from_millis(2_125),
+                    click: Click::Chord,
+                    pos: Pos::new(1, 1),
+                },
+            ],
+        }
+    }
+
+    fn date() -> Date {
+        Date::parse("2026-03-07").unwrap()
+    }
+
+    #[test]
+    fn reads_what_it_writes() {
+        let replay = replay("3x3\n..*\n...\n...\n", Rules::default());
+        let raw = replay.to_raw(date()).unwrap();
+        assert!(raw.contains("Level: Custom\n"));
+        assert!(raw.contains("Board:\n00*\n000\n000\nEvents:\n"));
+        assert!(raw.contains("1.500 rc 3 1 (40 8)\n"));
+
+        let read = Replay::parse_raw("test", &raw).unwrap();
+        assert_eq!(read.layout, replay.layout);
+        assert_eq!(read.moves, replay.moves);
+    }
+
+    #[test]
+    fn only_writes_classic_games() {
+        let lives = Rules {
+            lives: 3,
+            ..Rules::default()
+        };
+        assert!(replay("3x3\n..*\n...\n...\n", lives)
+            .to_raw(date())
+            .is_err());
+        let stacked = replay("3x3\n..2\n...\n...\n", Rules::default());
+        assert!(!stacked.is_classic());
+    }
+
+    #[test]
+    fn reads_both_buttons_as_a_chord() {
+        let raw = "Width: 3\nHeight: 2\nBoard:\n*00\n000\nEvents:\n\
+                   0.000 start\n\
+                   0.100 lc 3 2 (40 24)\n\
+                   0.200 lr 3 2 (40 24)\n\
+                   0.300 mv 1 1 (8 8)\n\
+                   0.400 rc 1 1 (8 8)\n\
+                   0.450 rr 1 1 (8 8)\n\
+                   0.500 lc 2 2\n\
+                   0.600 rc 2 2\n\
+                   0.700 rr 2 2\n\
+                   0.800 lr 2 2\n\
+                   0.900 lc 9 9\n\
+                   0.950 lr 9 9\n";
+        let read = Replay::parse_raw("test", raw).unwrap();
+        let clicks: Vec<(Click, Pos)> = read.moves.iter().map(|e| (e.click, e.pos)).collect();
+        assert_eq!(
+            clicks,
+            [
+                (Click::Reveal, Pos::new(1, 2)),
+                (Click::Flag, Pos::new(0, 0)),
+                (Click::Chord, Pos::new(1, 1)),
+            ]
+        );
+        assert_eq!(read.moves[2].time, Duration::from_millis(700));
+    }
+
+    #[test]
+    fn rejects_broken_replays() {
+        for raw in [
+            "Height: 2\nBoard:\n*0\n00\nEvents:\n",
+            "Width: 2\nHeight: 2\nBoard:\n*0\n00\n",
+            "Width: 2\nHeight: 99999999\nBoard:\n*0\n00\nEvents:\n",
+        ] {
+            assert!(Replay::parse_raw("test", raw).is_err(), "{raw}");
+        }
+        assert!(Replay::load(Path::new("game.avf")).is_err());
+        assert!(Replay::is_replay(Path::new("game.RMV")));
+        assert!(!Replay::is_replay(Path::new("board.txt")));
+    }
+
+    #[test]
+    fn never_overwrites_a_replay() {
+        let dir = std::env::temp_dir().join(format!("replays-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = free_path(&dir, "code");
+        assert_eq!(first, dir.join("code.raw"));
+        fs::write(&first, "").unwrap();
+        assert_eq!(free_path(&dir, "code"), dir.join("code-2.raw"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,503 @@
+use std::{fmt, time::Duration};
+
+use super::{Dim, Pos};
+
+/// What is guaranteed about the first tile the player reveals.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FirstClick {
+    /// Only the clicked tile is safe. If a mine was placed there it is moved
+    /// to the top left corner, like the original Windows game did.
+    Classic,
+    /// The clicked tile and every tile it counts are kept free of mines, so
+    /// the first click always starts an opening.
+    Zero,
+    /// The 3x3 block around the clicked tile is free of mines.
+    #[default]
+    Safe3x3,
+    /// No protection at all: the first click can lose the game.
+    None,
+}
+
+impl FirstClick {
+    pub const ALL: [FirstClick; 4] = [
+        FirstClick::Classic,
+        FirstClick::Zero,
+        FirstClick::Safe3x3,
+        FirstClick::None,
+    ];
+
+    /// A stable name used in files and on the command line.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Zero => "zero",
+            Self::Safe3x3 => "safe3x3",
+            Self::None => "none",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<FirstClick> {
+        Self::ALL.into_iter().find(|e| e.id() == id)
+    }
+
+    #[must_use]
+    pub fn next(self) -> FirstClick {
+        let i = Self::ALL.iter().position(|&e| e == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for FirstClick {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Classic => "Classic",
+            Self::Zero => "Zero",
+            Self::Safe3x3 => "Safe 3x3",
+            Self::None => "None",
+        })
+    }
+}
+
+/// The shape of the tiles and therefore which tiles touch each other.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Topology {
+    #[default]
+    Square,
+    /// Pointy-topped hexagons with every odd row shifted right by half a tile.
+    Hex,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 2] = [Topology::Square, Topology::Hex];
+
+    const SQUARE: [(i32, i32); 8] = [
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, -1),
+        (0, 1),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+    ];
+    const HEX_EVEN: [(i32, i32); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+    const HEX_ODD: [(i32, i32); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+    /// The (row, col) offsets from `pos` to the tiles touching it.
+    fn offsets(self, pos: Pos) -> &'static [(i32, i32)] {
+        match self {
+            Self::Square => &Self::SQUARE,
+            Self::Hex if pos.row % 2 == 0 => &Self::HEX_EVEN,
+            Self::Hex => &Self::HEX_ODD,
+        }
+    }
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Square => "square",
+            Self::Hex => "hex",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Topology> {
+        Self::ALL.into_iter().find(|e| e.id() == id)
+    }
+
+    #[must_use]
+    pub fn next(self) -> Topology {
+        let i = Self::ALL.iter().position(|&e| e == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Square => "Square",
+            Self::Hex => "Hex",
+        })
+    }
+}
+
+/// Which tiles a number counts the mines of.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Neighborhood {
+    /// The tiles touching it, as decided by the topology.
+    #[default]
+    Standard,
+    /// Only the four orthogonally adjacent tiles.
+    Cross,
+    /// The eight tiles a chess knight could move to.
+    Knight,
+    /// Every tile within two steps.
+    Radius2,
+}
+
+impl Neighborhood {
+    pub const ALL: [Neighborhood; 4] = [
+        Neighborhood::Standard,
+        Neighborhood::Cross,
+        Neighborhood::Knight,
+        Neighborhood::Radius2,
+    ];
+
+    const CROSS: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+    const KNIGHT: [(i32, i32); 8] = [
+        (-2, -1),
+        (-2, 1),
+        (-1, -2),
+        (-1, 2),
+        (1, -2),
+        (1, 2),
+        (2, -1),
+        (2, 1),
+    ];
+    const SQUARE_RADIUS2: [(i32, i32); 24] = [
+        (-2, -2),
+        (-2, -1),
+        (-2, 0),
+        (-2, 1),
+        (-2, 2),
+        (-1, -2),
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (-1, 2),
+        (0, -2),
+        (0, -1),
+        (0, 1),
+        (0, 2),
+        (1, -2),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (2, -2),
+        (2, -1),
+        (2, 0),
+        (2, 1),
+        (2, 2),
+    ];
+    const HEX_RADIUS2_EVEN: [(i32, i32); 18] = [
+        (-2, -1),
+        (-2, 0),
+        (-2, 1),
+        (-1, -2),
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, -2),
+        (0, -1),
+        (0, 1),
+        (0, 2),
+        (1, -2),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (2, -1),
+        (2, 0),
+        (2, 1),
+    ];
+    const HEX_RADIUS2_ODD: [(i32, i32); 18] = [
+        (-2, -1),
+        (-2, 0),
+        (-2, 1),
+        (-1, -1),
+        (-1, 0),
+        (-1, 1),
+        (-1, 2),
+        (0, -2),
+        (0, -1),
+        (0, 1),
+        (0, 2),
+        (1, -1),
+        (1, 0),
+        (1, 1),
+        (1, 2),
+        (2, -1),
+        (2, 0),
+        (2, 1),
+    ];
+
+    /// The cross and knight's move only make sense on a square grid.
+    pub fn supports(self, topology: Topology) -> bool {
+        topology == Topology::Square || matches!(self, Self::Standard | Self::Radius2)
+    }
+
+    fn offsets(self, topology: Topology, pos: Pos) -> &'static [(i32, i32)] {
+        match (self, topology) {
+            (Self::Standard, _) => topology.offsets(pos),
+            (Self::Cross, _) => &Self::CROSS,
+            (Self::Knight, _) => &Self::KNIGHT,
+            (Self::Radius2, Topology::Square) => &Self::SQUARE_RADIUS2,
+            (Self::Radius2, Topology::Hex) if pos.row % 2 == 0 => &Self::HEX_RADIUS2_EVEN,
+            (Self::Radius2, Topology::Hex) => &Self::HEX_RADIUS2_ODD,
+        }
+    }
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Cross => "cross",
+            Self::Knight => "knight",
+            Self::Radius2 => "radius2",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Neighborhood> {
+        Self::ALL.into_iter().find(|e| e.id() == id)
+    }
+
+    /// The next neighborhood that can be used with `topology`.
+    #[must_use]
+    pub fn next(self, topology: Topology) -> Neighborhood {
+        let i = Self::ALL.iter().position(|&e| e == self).unwrap();
+        (1..=Self::ALL.len())
+            .map(|n| Self::ALL[(i + n) % Self::ALL.len()])
+            .find(|e| e.supports(topology))
+            .unwrap()
+    }
+}
+
+impl fmt::Display for Neighborhood {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Standard => "Standard",
+            Self::Cross => "Cross",
+            Self::Knight => "Knight",
+            Self::Radius2 => "Radius 2",
+        })
+    }
+}
+
+/// Everything besides the size that changes how a board is generated or played.
+/// It is kept with the board so results can tell the variants apart.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rules {
+    pub first_click: FirstClick,
+    pub topology: Topology,
+    /// Opposite edges of the board touch, turning it into a torus.
+    pub wrap: bool,
+    pub neighborhood: Neighborhood,
+    /// Tiles can hold up to three mines, and numbers count every one of them.
+    pub multi_mine: bool,
+    /// How many mines can be hit before the game is lost. Every mine but the
+    /// last one is left uncovered and costs a time penalty instead.
+    pub lives: u8,
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules {
+            first_click: FirstClick::default(),
+            topology: Topology::default(),
+            wrap: false,
+            neighborhood: Neighborhood::default(),
+            multi_mine: false,
+            lives: 1,
+        }
+    }
+}
+
+impl Rules {
+    /// The choices of lives offered in the menu.
+    pub const LIVES: [u8; 3] = [1, 3, 5];
+
+    /// The time added for every mine hit while lives remain.
+    pub const LIFE_PENALTY: Duration = Duration::from_secs(10);
+
+    pub(super) const MAX_NEIGHBORS: usize = 24;
+
+    /// How these rules differ from the default ones, as short words that
+    /// tell results apart, e.g. `hex` or `lives3`. Empty for the default.
+    pub fn tags(self) -> Vec<String> {
+        let default = Rules::default();
+        let mut tags = vec![];
+        if self.first_click != default.first_click {
+            tags.push(format!("first-{}", self.first_click.id()));
+        }
+        if self.topology != default.topology {
+            tags.push(self.topology.id().to_string());
+        }
+        if self.wrap {
+            tags.push("wrap".to_string());
+        }
+        if self.neighborhood != default.neighborhood {
+            tags.push(self.neighborhood.id().to_string());
+        }
+        if self.multi_mine {
+            tags.push("multi".to_string());
+        }
+        if self.lives != default.lives {
+            tags.push(format!("lives{}", self.lives));
+        }
+        tags
+    }
+
+    /// The most mines, and therefore flags, a single tile can hold.
+    pub fn tile_mines(self) -> u8 {
+        if self.multi_mine {
+            3
+        } else {
+            1
+        }
+    }
+
+    /// Whether the top and bottom rows touch. Hex rows alternate their
+    /// offset, so they can only wrap when there is an even number of them.
+    pub fn wraps_rows(self, dim: Dim) -> bool {
+        self.wrap && (self.topology != Topology::Hex || dim.rows % 2 == 0)
+    }
+
+    /// Maps a position that may lie past the edges onto the board, or returns
+    /// `None` if it is not part of it.
+    pub fn wrap_pos(self, dim: Dim, pos: Pos) -> Option<Pos> {
+        let pos = Pos::new(
+            if self.wraps_rows(dim) {
+                pos.row.rem_euclid(dim.rows)
+            } else {
+                pos.row
+            },
+            if self.wrap {
+                pos.col.rem_euclid(dim.cols)
+            } else {
+                pos.col
+            },
+        );
+        dim.contains(pos).then_some(pos)
+    }
+
+    /// The (row, col) offsets from `pos` to the tiles its number counts,
+    /// ignoring the edges of the board.
+    pub fn offsets(self, pos: Pos) -> &'static [(i32, i32)] {
+        self.neighborhood.offsets(self.topology, pos)
+    }
+
+    /// The tiles around `pos` that lie on the board and are counted by its
+    /// number. Everything that needs to know which tiles touch goes through here.
+    pub fn neighbors(self, dim: Dim, pos: Pos) -> impl Iterator<Item = Pos> {
+        // On narrow wrapping boards several offsets can land on the same tile,
+        // which must still only be counted once.
+        let mut adjacent = [pos; Self::MAX_NEIGHBORS];
+        let mut len = 0;
+        for &(row, col) in self.offsets(pos) {
+            if let Some(adj) = self.wrap_pos(dim, Pos::new(pos.row + row, pos.col + col)) {
+                if adj != pos && !adjacent[..len].contains(&adj) {
+                    adjacent[len] = adj;
+                    len += 1;
+                }
+            }
+        }
+        adjacent.into_iter().take(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_tell_variants_apart() {
+        assert!(Rules::default().tags().is_empty());
+        let rules = Rules {
+            first_click: FirstClick::Zero,
+            topology: Topology::Hex,
+            wrap: true,
+            neighborhood: Neighborhood::Knight,
+            multi_mine: true,
+            lives: 3,
+        };
+        assert_eq!(
+            rules.tags(),
+            ["first-zero", "hex", "wrap", "knight", "multi", "lives3"]
+        );
+    }
+
+    fn neighbors(rules: Rules, dim: Dim, row: i32, col: i32) -> Vec<(i32, i32)> {
+        let mut adjacent: Vec<_> = rules
+            .neighbors(dim, Pos::new(row, col))
+            .map(|pos| (pos.row, pos.col))
+            .collect();
+        adjacent.sort_unstable();
+        adjacent
+    }
+
+    #[test]
+    fn hex_rows_alternate_their_neighbors() {
+        let hex = Rules {
+            topology: Topology::Hex,
+            ..Rules::default()
+        };
+        let dim = Dim::new(5, 5);
+        assert_eq!(
+            neighbors(hex, dim, 2, 2),
+            [(1, 1), (1, 2), (2, 1), (2, 3), (3, 1), (3, 2)]
+        );
+        assert_eq!(
+            neighbors(hex, dim, 1, 2),
+            [(0, 2), (0, 3), (1, 1), (1, 3), (2, 2), (2, 3)]
+        );
+        // Touching goes both ways.
+        for row in 0..dim.rows {
+            for col in 0..dim.cols {
+                for (r, c) in neighbors(hex, dim, row, col) {
+                    assert!(neighbors(hex, dim, r, c).contains(&(row, col)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn narrow_wrapped_boards_count_each_tile_once() {
+        let wrap = Rules {
+            wrap: true,
+            ..Rules::default()
+        };
+        assert_eq!(neighbors(wrap, Dim::new(1, 2), 0, 0), [(0, 1)]);
+        assert_eq!(neighbors(wrap, Dim::new(1, 4), 0, 0), [(0, 1), (0, 3)]);
+        assert_eq!(
+            neighbors(wrap, Dim::new(2, 2), 0, 0),
+            [(0, 1), (1, 0), (1, 1)]
+        );
+        assert_eq!(
+            neighbors(wrap, Dim::new(2, 2), 1, 1),
+            [(0, 0), (0, 1), (1, 0)]
+        );
+        let radius2 = Rules {
+            neighborhood: Neighborhood::Radius2,
+            ..wrap
+        };
+        assert_eq!(neighbors(radius2, Dim::new(2, 2), 0, 0).len(), 3);
+        assert!(neighbors(wrap, Dim::new(1, 1), 0, 0).is_empty());
+    }
+
+    #[test]
+    fn neighborhoods_count_their_tiles() {
+        let dim = Dim::new(7, 7);
+        let cases = [
+            (Topology::Square, Neighborhood::Standard, 8, 3),
+            (Topology::Square, Neighborhood::Cross, 4, 2),
+            (Topology::Square, Neighborhood::Knight, 8, 2),
+            (Topology::Square, Neighborhood::Radius2, 24, 8),
+            (Topology::Hex, Neighborhood::Standard, 6, 2),
+            (Topology::Hex, Neighborhood::Radius2, 18, 6),
+        ];
+        for (topology, neighborhood, middle, corner) in cases {
+            let rules = Rules {
+                topology,
+                neighborhood,
+                ..Rules::default()
+            };
+            assert_eq!(
+                neighbors(rules, dim, 3, 3).len(),
+                middle,
+                "{topology} {neighborhood}"
+            );
+            assert_eq!(
+                neighbors(rules, dim, 0, 0).len(),
+                corner,
+                "{topology} {neighborhood}"
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{fmt, time::Duration};
+
+use super::board::Board;
+
+/// Writes a time in seconds with milliseconds, e.g. `12.345`.
+pub fn seconds(time: Duration) -> String {
+    format!("{}.{:03}", time.as_secs(), time.subsec_millis())
+}
+
+/// Every click made in a game, by kind.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Clicks {
+    /// Reveals of hidden tiles.
+    pub left: u32,
+    /// Flags, whichever way they were placed.
+    pub right: u32,
+    /// Reveals on numbers, which open the tiles around them.
+    pub chord: u32,
+    /// The clicks that changed the board.
+    pub effective: u32,
+}
+
+impl Clicks {
+    pub fn total(self) -> u32 {
+        self.left + self.right + self.chord
+    }
+}
+
+/// The statistics speedrunners compare games by, worked out from the board
+/// and the clicks made on it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Stats {
+    pub time: Duration,
+    /// The fewest clicks that clear the board without flagging.
+    pub three_bv: u32,
+    /// How much of the 3BV was cleared, which is all of it for a win.
+    pub solved: u32,
+    pub clicks: Clicks,
+    pub openings: u32,
+    pub islands: u32,
+}
+
+impl Stats {
+    pub fn new(board: &Board, clicks: Clicks, time: Duration) -> Stats {
+        let (three_bv, solved) = board.three_bv();
+        Stats {
+            time,
+            three_bv,
+            solved,
+            clicks,
+            openings: board.openings(),
+            islands: board.islands(),
+        }
+    }
+
+    pub fn three_bv_per_second(&self) -> f64 {
+        let secs = self.time.as_secs_f64();
+        if secs > 0.0 {
+            f64::from(self.solved) / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Solved 3BV per effective click. Flagging lowers it, chording can push
+    /// it past 100%.
+    pub fn efficiency(&self) -> f64 {
+        ratio(self.solved, self.clicks.effective)
+    }
+
+    /// The index of efficiency: solved 3BV per click, wasted clicks included.
+    pub fn ioe(&self) -> f64 {
+        ratio(self.solved, self.clicks.total())
+    }
+
+    /// Reads the statistics as written by `Display`.
+    pub fn parse(words: &[&str]) -> Option<Stats> {
+        let [time, ref numbers @ ..] = words[..] else {
+            return None;
+        };
+        let numbers = numbers
+            .iter()
+            .map(|e| e.parse().ok())
+            .collect::<Option<Vec<u32>>>()?;
+        let [three_bv, solved, left, right, chord, effective, openings, islands] = numbers[..]
+        else {
+            return None;
+        };
+        Some(Stats {
+            time: Duration::from_millis(time.parse().ok()?),
+            three_bv,
+            solved,
+            clicks: Clicks {
+                left,
+                right,
+                chord,
+                effective,
+            },
+            openings,
+            islands,
+        })
+    }
+
+    /// The statistics as lines for the end screen.
+    pub fn lines(&self) -> Vec<String> {
+        vec![
+            format!(
+                "3BV: {}/{} | 3BV/s: {:.2}",
+                self.solved,
+                self.three_bv,
+                self.three_bv_per_second()
+            ),
+            format!(
+                "Clicks: {} ({} left, {} right, {} chord)",
+                self.clicks.total(),
+                self.clicks.left,
+                self.clicks.right,
+                self.clicks.chord
+            ),
+            format!(
+                "Efficiency: {:.0}% | IOE: {:.2}",
+                self.efficiency() * 100.0,
+                self.ioe()
+            ),
+            format!("Openings: {} | Islands: {}", self.openings, self.islands),
+        ]
+    }
+}
+
+/// Writes the time in milliseconds followed by the counts, separated by
+/// spaces.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {} {} {} {} {}",
+            self.time.as_millis(),
+            self.three_bv,
+            self.solved,
+            self.clicks.left,
+            self.clicks.right,
+            self.clicks.chord,
+            self.clicks.effective,
+            self.openings,
+            self.islands
+        )
+    }
+}
+
+fn ratio(a: u32, b: u32) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        f64::from(a) / f64::from(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> Stats {
+        Stats {
+            time: Duration::from_millis(12_345),
+            three_bv: 10,
+            solved: 8,
+            clicks: Clicks {
+                left: 6,
+                right: 3,
+                chord: 1,
+                effective: 8,
+            },
+            openings: 2,
+            islands: 1,
+        }
+    }
+
+    #[test]
+    fn parses_what_it_writes() {
+        let text = stats().to_string();
+        assert_eq!(text, "12345 10 8 6 3 1 8 2 1");
+        let words: Vec<&str> = text.split_whitespace().collect();
+        assert_eq!(Stats::parse(&words), Some(stats()));
+    }
+
+    #[test]
+    fn rejects_bad_statistics() {
+        for text in [
+            "",
+            "12345 10 8 6 3 1 8 2",
+            "12345 10 8 6 3 1 8 2 1 0",
+            "1.5 10 8 6 3 1 8 2 1",
+        ] {
+            let words: Vec<&str> = text.split_whitespace().collect();
+            assert_eq!(Stats::parse(&words), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn rates_clicks() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        let stats = stats();
+        assert!(close(stats.efficiency(), 1.0));
+        assert!(close(stats.ioe(), 0.8));
+        assert!(close(stats.three_bv_per_second(), 8.0 / 12.345));
+        assert!(close(Stats::default().efficiency(), 0.0));
+        assert!(close(Stats::default().three_bv_per_second(), 0.0));
+    }
+
+    #[test]
+    fn writes_seconds_with_milliseconds() {
+        assert_eq!(seconds(Duration::from_millis(12_045)), "12.045");
+        assert_eq!(seconds(Duration::ZERO), "0.000");
+    }
+}
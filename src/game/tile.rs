@@ -3,90 +3,209 @@ use sdl2::{
     render::{Texture, WindowCanvas},
 };
 
+use super::rules::Topology;
+
 pub const TILE_SIZE: i32 = 24;
+/// Hex tiles are as wide as square ones but taller, and each row overlaps the
+/// pointed bottom of the row above it.
+pub const HEX_HEIGHT: i32 = 27;
+pub const HEX_ROW_STEP: i32 = 20;
 
-#[derive(Clone)]
-pub struct Tile {
-    pub value: Option<u8>,
-    pub is_mine: bool,
-    pub is_flagged: bool,
-    pub is_mined: bool,
-    pub rect: Rect,
-}
+const VALUE: u16 = 0b0000_0000_0111_1111;
+const MINES: u16 = 0b0000_0001_1000_0000;
+const FLAGS: u16 = 0b0000_0110_0000_0000;
+const MINED: u16 = 0b0000_1000_0000_0000;
+const WALL: u16 = 0b0001_0000_0000_0000;
+const EXPLODED: u16 = 0b0010_0000_0000_0000;
+const MINES_SHIFT: u32 = MINES.trailing_zeros();
+const FLAGS_SHIFT: u32 = FLAGS.trailing_zeros();
+
+/// A single cell packed into two bytes: the low bits hold the number of
+/// adjacent mines, followed by how many mines and flags the tile holds and
+/// its revealed/wall state.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct Tile(u16);
 
 impl Tile {
-    pub fn new(value: Option<u8>) -> Tile {
-        Tile {
-            value,
-            is_mine: value.is_none(),
-            is_flagged: false,
-            is_mined: false,
-            rect: Rect::new(0, 16, 16, 16),
-        }
+    pub fn is_mine(self) -> bool {
+        self.0 & MINES != 0
+    }
+
+    /// How many mines are hidden in this tile, which is more than one only
+    /// with multi-mine rules.
+    pub fn mines(self) -> u8 {
+        u8::try_from((self.0 & MINES) >> MINES_SHIFT).unwrap()
     }
 
-    pub fn set_mine(&mut self) {
-        self.value = None;
-        self.is_mine = true;
+    pub fn is_flagged(self) -> bool {
+        self.0 & FLAGS != 0
+    }
+
+    pub fn flags(self) -> u8 {
+        u8::try_from((self.0 & FLAGS) >> FLAGS_SHIFT).unwrap()
+    }
+
+    pub fn is_mined(self) -> bool {
+        self.0 & MINED != 0
+    }
+
+    /// Walls are the holes in masked boards. They cannot be revealed or
+    /// flagged and are left out of every count.
+    pub fn is_wall(self) -> bool {
+        self.0 & WALL != 0
+    }
+
+    /// A mine that was revealed. This ends the game unless lives remain.
+    pub fn is_exploded(self) -> bool {
+        self.0 & EXPLODED != 0
+    }
+
+    pub fn value(self) -> Option<u8> {
+        (!self.is_mine()).then(|| u8::try_from(self.0 & VALUE).unwrap())
+    }
+
+    /// Hides one more mine in this tile.
+    pub fn add_mine(&mut self) {
+        let mines = u16::from(self.mines()) + 1;
+        self.0 = (self.0 & !(VALUE | MINES)) | ((mines << MINES_SHIFT) & MINES);
     }
 
     pub fn set_value(&mut self, value: u8) {
-        self.is_mine = false;
-        self.value = Some(value);
+        self.0 = (self.0 & !(VALUE | MINES)) | (u16::from(value) & VALUE);
+    }
+
+    pub fn set_flags(&mut self, flags: u8) {
+        self.0 = (self.0 & !FLAGS) | ((u16::from(flags) << FLAGS_SHIFT) & FLAGS);
+    }
+
+    pub fn set_wall(&mut self) {
+        self.0 = WALL;
     }
 
     pub fn mine(&mut self) -> bool {
-        self.is_mined = true;
-        if self.is_mine {
-            self.rect.x = 32;
-            true
+        self.0 |= MINED;
+        self.is_mine()
+    }
+
+    pub fn explode(&mut self) {
+        self.0 |= MINED | EXPLODED;
+    }
+
+    /// The (column, row) of the sprite for this tile in the spritesheet.
+    fn sprite(self) -> (i32, i32) {
+        if self.is_mined() {
+            match self.value() {
+                Some(value @ 0..=8) => (i32::from(value), 0),
+                // Numbers above 8 continue in rows of nine below the others.
+                Some(value) => (i32::from(value - 9) % 9, 2 + i32::from(value - 9) / 9),
+                // Exploded mines are on red tiles in the last row.
+                None => (i32::from(self.mines()), 9),
+            }
+        } else if self.is_flagged() {
+            match self.flags() {
+                1 => (1, 1),
+                flags => (3 + i32::from(flags), 1),
+            }
         } else {
-            self.rect.x = 16 * i32::from(self.value.unwrap());
-            self.rect.y = 0;
-            false
+            (0, 1)
         }
     }
 
-    pub fn flag(&mut self) -> Option<bool> {
-        if self.is_mined {
-            return None;
-        }
-        self.is_flagged = !self.is_flagged;
-        if self.is_flagged {
-            self.rect.x = 16;
-            Some(false)
-        } else {
-            self.rect.x = 0;
-            Some(true)
+    fn mines_sprite(self) -> (i32, i32) {
+        match self.mines() {
+            1 => (2, 1),
+            mines => (5 + i32::from(mines), 1),
         }
     }
 
     pub fn render(
-        &self,
+        self,
         canvas: &mut WindowCanvas,
         tex: &Texture,
-        x: i32,
-        y: i32,
+        topology: Topology,
+        dest: Rect,
         status: Option<bool>,
     ) -> Result<(), String> {
-        let mut rect = self.rect;
+        if self.is_wall() {
+            return Ok(());
+        }
+        let mut sprite = self.sprite();
         if let Some(won) = status {
-            if won {
-                rect.y = 16;
-                rect.x = if self.is_mine { 64 } else { 0 };
-            } else if self.is_flagged && !self.is_mine {
-                rect.x = 48;
-                rect.y = 16;
-            } else if !self.is_flagged && self.is_mine {
-                rect.x = 32;
-                rect.y = 16;
+            if self.is_exploded() {
+            } else if won {
+                sprite = (if self.is_mine() { 4 } else { 0 }, 1);
+            } else if self.is_flagged() && !self.is_mine() {
+                sprite = (3, 1);
+            } else if !self.is_flagged() && self.is_mine() {
+                sprite = self.mines_sprite();
             }
         }
 
+        let height = match topology {
+            Topology::Square => 16,
+            Topology::Hex => 18,
+        };
         canvas.copy(
             tex,
-            rect,
-            Rect::new(x, y, TILE_SIZE as u32, TILE_SIZE as u32),
+            Rect::new(
+                sprite.0 * 16,
+                sprite.1 * height,
+                16,
+                u32::try_from(height).unwrap(),
+            ),
+            dest,
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::rules::Rules;
+
+    #[test]
+    fn fields_keep_to_their_bits() {
+        // The most a number can reach is every neighbor holding three mines,
+        // well within the value bits.
+        assert!(Rules::MAX_NEIGHBORS * 3 <= usize::from(VALUE));
+        let mut tile = Tile::default();
+        tile.set_value(u8::try_from(VALUE).unwrap());
+        tile.set_flags(3);
+        assert_eq!(tile.value(), Some(127));
+        assert_eq!(tile.flags(), 3);
+        assert!(!tile.is_mine() && !tile.is_mined() && !tile.is_wall());
+
+        for mines in 1..=3 {
+            tile.add_mine();
+            assert_eq!(tile.mines(), mines);
+            assert_eq!(tile.value(), None);
+            assert_eq!(tile.flags(), 3);
+        }
+
+        assert!(tile.mine());
+        assert!(tile.is_mined() && !tile.is_exploded());
+        tile.explode();
+        assert!(tile.is_exploded());
+        assert_eq!(tile.mines(), 3);
+        assert_eq!(tile.flags(), 3);
+        assert!(!tile.is_wall());
+
+        tile.set_value(0);
+        assert_eq!(tile.value(), Some(0));
+        assert_eq!(tile.mines(), 0);
+        tile.set_flags(0);
+        assert!(!tile.is_flagged());
+        assert!(tile.is_mined() && tile.is_exploded());
+
+        tile.set_wall();
+        assert!(tile.is_wall());
+        assert!(
+            tile == {
+                let mut wall = Tile::default();
+                wall.set_wall();
+                wall
+            }
+        );
+        assert_eq!(tile.value(), Some(0));
+    }
+}
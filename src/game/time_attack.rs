@@ -0,0 +1,109 @@
+use std::{mem, time::Duration};
+
+use sdl2::{
+    render::{TextureCreator, WindowCanvas},
+    ttf::Font,
+    video::WindowContext,
+};
+
+use crate::{
+    stopwatch::Stopwatch,
+    ui::{end_menu::Playfield, text::Text},
+};
+
+use super::{board::Size, map::Map, rules::Rules, Stage};
+
+/// How long a time attack run lasts.
+pub const BUDGET: Duration = Duration::from_mins(3);
+const SIZE: Size = Size::Small;
+
+/// Clearing as many small boards as possible before the clock runs out. The
+/// boards are laid out from one seed, so a run can be played again exactly.
+pub struct TimeAttack<'a> {
+    pub map: Map<'a>,
+    pub seed: u64,
+    rules: Rules,
+    /// The boards that were won. Losing one just deals the next.
+    pub cleared: u32,
+    // Counts every board dealt, which also picks its seed.
+    boards: u64,
+    cleared_text: Text<'a>,
+}
+
+impl<'a> TimeAttack<'a> {
+    pub fn new(
+        seed: u64,
+        rules: Rules,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        font: &'a Font,
+    ) -> TimeAttack<'a> {
+        let mut map = Map::new(SIZE, rules, None, tex_creator, font);
+        map.reseed(seed);
+        map.stopwatch = Stopwatch::countdown(BUDGET);
+        TimeAttack {
+            cleared_text: Text::new(
+                0,
+                60,
+                0,
+                0,
+                tex_creator,
+                "Cleared: 0",
+                font,
+                u32::try_from(map.width()).unwrap(),
+                u32::try_from(map.height()).unwrap(),
+            ),
+            map,
+            seed,
+            rules,
+            cleared: 0,
+            boards: 1,
+        }
+    }
+
+    /// Moves on once the current board is over. The clock keeps running
+    /// from one board to the next.
+    pub fn next_board(&mut self, tex_creator: &'a TextureCreator<WindowContext>, font: &'a Font) {
+        if self.map.check_state() == Stage::Win {
+            self.cleared += 1;
+            self.cleared_text
+                .set_text(&format!("Cleared: {}", self.cleared));
+        }
+        let stopwatch = mem::take(&mut self.map.stopwatch);
+        self.map = Map::new(SIZE, self.rules, None, tex_creator, font);
+        self.map.reseed(self.seed.wrapping_add(self.boards));
+        self.map.stopwatch = stopwatch;
+        self.boards += 1;
+    }
+}
+
+impl<'a> Playfield<'a> for TimeAttack<'a> {
+    fn width(&self) -> i32 {
+        self.map.width()
+    }
+
+    fn height(&self) -> i32 {
+        self.map.height()
+    }
+
+    fn render(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        font: &Font,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        status: Option<bool>,
+    ) -> Result<(), String> {
+        self.map.render(canvas, font, tex_creator, status)?;
+        if status.is_none() {
+            self.cleared_text.render(canvas, font, tex_creator)?;
+        }
+        Ok(())
+    }
+
+    fn summary(&self) -> String {
+        format!("Boards cleared: {} | Seed: {}", self.cleared, self.seed)
+    }
+
+    fn name(&self) -> String {
+        "Time Attack".to_string()
+    }
+}
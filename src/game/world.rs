@@ -0,0 +1,455 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+    time::Duration,
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::{
+    rules::{FirstClick, Neighborhood, Rules, Topology},
+    tile::Tile,
+    Pos, Stage,
+};
+
+/// Chunks are square blocks of this many tiles on each side.
+pub const CHUNK: i32 = 16;
+pub const CHUNK_TILES: usize = (CHUNK * CHUNK) as usize;
+/// The same density as a normal board.
+const CHUNK_MINES: usize = 40;
+
+/// Splits a tile position into the chunk it lies in and its row-major index
+/// within that chunk.
+fn locate(pos: Pos) -> (Pos, usize) {
+    let chunk = Pos::new(pos.row.div_euclid(CHUNK), pos.col.div_euclid(CHUNK));
+    let index = pos.row.rem_euclid(CHUNK) * CHUNK + pos.col.rem_euclid(CHUNK);
+    (chunk, usize::try_from(index).unwrap())
+}
+
+/// An endless board. Chunks are only generated once something needs to know
+/// where their mines are, and always come out the same for the same seed, so
+/// only the chunks the player has touched have to be saved.
+pub struct World {
+    pub seed: u64,
+    pub rules: Rules,
+    chunks: HashMap<Pos, Vec<Tile>>,
+    // The first click, which decides the tiles the first click policy keeps
+    // free of mines. Kept so chunks generated later still leave them free.
+    first: Option<Pos>,
+    lost: bool,
+    /// Every tile revealed so far, which is the score.
+    pub revealed: u32,
+    /// How many of the revealed tiles are zeros.
+    pub zeros: u32,
+    pub flags: u32,
+}
+
+impl World {
+    /// Endless boards are always square grids with one mine per tile, no
+    /// edges to wrap and a single life.
+    pub fn new(seed: u64, rules: Rules) -> World {
+        World {
+            seed,
+            rules: Rules {
+                topology: Topology::Square,
+                wrap: false,
+                multi_mine: false,
+                lives: 1,
+                ..rules
+            },
+            chunks: HashMap::new(),
+            first: None,
+            lost: false,
+            revealed: 0,
+            zeros: 0,
+            flags: 0,
+        }
+    }
+
+    /// The first tile that was clicked, if the game has started.
+    pub fn first(&self) -> Option<Pos> {
+        self.first
+    }
+
+    /// Whether the first click policy keeps mines off `pos`. There is no
+    /// corner to move a mine to, so classic only keeps the clicked tile free.
+    fn protected(&self, first: Pos, pos: Pos) -> bool {
+        match self.rules.first_click {
+            FirstClick::None => false,
+            FirstClick::Classic => pos == first,
+            FirstClick::Zero => pos == first || self.neighbors(first).any(|e| e == pos),
+            FirstClick::Safe3x3 => {
+                (pos.row - first.row).abs() <= 1 && (pos.col - first.col).abs() <= 1
+            }
+        }
+    }
+
+    fn generate(&self, chunk: Pos) -> Vec<Tile> {
+        // Mix the chunk position into the seed so neighboring chunks differ.
+        let key = u64::from(chunk.row.cast_unsigned()) << 32 | u64::from(chunk.col.cast_unsigned());
+        let mut rng = StdRng::seed_from_u64(self.seed ^ key.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+
+        let origin = Pos::new(chunk.row * CHUNK, chunk.col * CHUNK);
+        let free: Vec<usize> = (0..CHUNK_TILES)
+            .filter(|&i| {
+                let i = i32::try_from(i).unwrap();
+                let pos = Pos::new(origin.row + i / CHUNK, origin.col + i % CHUNK);
+                self.first.is_none_or(|first| !self.protected(first, pos))
+            })
+            .collect();
+
+        let mut tiles = vec![Tile::default(); CHUNK_TILES];
+        for &i in free.choose_multiple(&mut rng, CHUNK_MINES) {
+            tiles[i].add_mine();
+        }
+        tiles
+    }
+
+    fn chunk_mut(&mut self, chunk: Pos) -> &mut Vec<Tile> {
+        if !self.chunks.contains_key(&chunk) {
+            let tiles = self.generate(chunk);
+            self.chunks.insert(chunk, tiles);
+        }
+        self.chunks.get_mut(&chunk).unwrap()
+    }
+
+    /// The row-major tiles of a chunk, or `None` if it has not been
+    /// generated yet.
+    pub fn chunk(&self, chunk: Pos) -> Option<&[Tile]> {
+        self.chunks.get(&chunk).map(Vec::as_slice)
+    }
+
+    /// The tile at `pos`, or `None` if its chunk has not been generated yet,
+    /// in which case it is certainly still hidden.
+    pub fn get(&self, pos: Pos) -> Option<Tile> {
+        let (chunk, i) = locate(pos);
+        self.chunks.get(&chunk).map(|tiles| tiles[i])
+    }
+
+    fn tile_mut(&mut self, pos: Pos) -> &mut Tile {
+        let (chunk, i) = locate(pos);
+        &mut self.chunk_mut(chunk)[i]
+    }
+
+    pub fn neighbors(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        self.rules
+            .offsets(pos)
+            .iter()
+            .map(move |&(row, col)| Pos::new(pos.row + row, pos.col + col))
+    }
+
+    /// Counts the mines around `pos`, generating the chunks they lie in.
+    fn count(&mut self, pos: Pos) -> u8 {
+        let neighbors: Vec<Pos> = self.neighbors(pos).collect();
+        let mines = neighbors
+            .into_iter()
+            .filter(|&adj| self.tile_mut(adj).is_mine())
+            .count();
+        u8::try_from(mines).unwrap()
+    }
+
+    /// Reveals the tile at `pos`. The very first click also decides where the
+    /// mine free starting area is.
+    pub fn open(&mut self, pos: Pos) {
+        if self.first.is_none() {
+            self.first = Some(pos);
+        }
+        self.mine(pos);
+    }
+
+    /// Reveals a tile and the opening around it. Numbers are only worked out
+    /// once a tile is revealed, since most of the world never will be.
+    fn reveal(&mut self, pos: Pos) {
+        let tile = *self.tile_mut(pos);
+        if tile.is_flagged() || tile.is_mined() {
+            return;
+        }
+        if self.tile_mut(pos).mine() {
+            self.lost = true;
+            return;
+        }
+
+        let mut queue = VecDeque::from([pos]);
+        while let Some(pos) = queue.pop_front() {
+            let value = self.count(pos);
+            let tile = self.tile_mut(pos);
+            tile.set_value(value);
+            tile.mine();
+            self.revealed += 1;
+            if value != 0 {
+                continue;
+            }
+            self.zeros += 1;
+            let neighbors: Vec<Pos> = self.neighbors(pos).collect();
+            for adj in neighbors {
+                let tile = self.tile_mut(adj);
+                if !(tile.is_flagged() || tile.is_mined()) {
+                    // Marked right away so it is only queued once.
+                    tile.mine();
+                    queue.push_back(adj);
+                }
+            }
+        }
+    }
+
+    fn mine(&mut self, pos: Pos) {
+        let tile = *self.tile_mut(pos);
+        if tile.is_flagged() {
+        } else if tile.is_mined() {
+            let neighbors: Vec<Pos> = self.neighbors(pos).collect();
+            let flags = neighbors
+                .iter()
+                .filter(|&&e| self.tile_mut(e).is_flagged())
+                .count();
+            if u8::try_from(flags).unwrap() == tile.value().unwrap() {
+                for adj in neighbors {
+                    self.reveal(adj);
+                }
+            }
+        } else {
+            self.reveal(pos);
+        }
+    }
+
+    /// Flags or unflags a tile. Until the first click there are no mines to
+    /// flag, and generating a chunk this early would leave the first click
+    /// unprotected.
+    pub fn flag(&mut self, pos: Pos) {
+        if self.first.is_none() {
+            return;
+        }
+        let tile = self.tile_mut(pos);
+        if tile.is_mined() {
+            return;
+        }
+        if tile.is_flagged() {
+            tile.set_flags(0);
+            self.flags -= 1;
+        } else {
+            tile.set_flags(1);
+            self.flags += 1;
+        }
+    }
+
+    /// Writes down the seed, the rules, the first click, the time played and
+    /// every chunk the player has revealed or flagged something in.
+    /// Everything else comes back from the seed.
+    pub fn save(&self, elapsed: Duration) -> String {
+        let mut out = format!(
+            "seed {}\nneighborhood {}\nfirst_click {}\ntime {}\n",
+            self.seed,
+            self.rules.neighborhood.id(),
+            self.rules.first_click.id(),
+            elapsed.as_millis()
+        );
+        if let Some(first) = self.first {
+            writeln!(out, "first {} {}", first.row, first.col).unwrap();
+        }
+        let mut chunks: Vec<_> = self
+            .chunks
+            .iter()
+            .filter(|(_, tiles)| tiles.iter().any(|e| e.is_mined() || e.is_flagged()))
+            .collect();
+        chunks.sort_by_key(|(&chunk, _)| chunk);
+        for (chunk, tiles) in chunks {
+            let cells: String = tiles
+                .iter()
+                .map(|e| {
+                    if e.is_mined() {
+                        '#'
+                    } else if e.is_flagged() {
+                        'F'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            writeln!(out, "chunk {} {} {cells}", chunk.row, chunk.col).unwrap();
+        }
+        out
+    }
+
+    /// Reads a world back from `save`, along with the time played.
+    pub fn load(text: &str) -> Result<(World, Duration), String> {
+        let invalid = || "Invalid endless save".to_string();
+        let mut world = World::new(0, Rules::default());
+        let mut first_click = None;
+        let mut elapsed = None;
+        let mut revealed = vec![];
+        for line in text.lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| -> Result<i32, String> {
+                words
+                    .get(i)
+                    .and_then(|e| e.parse().ok())
+                    .ok_or_else(invalid)
+            };
+            match words.first() {
+                Some(&"seed") => {
+                    world.seed = words
+                        .get(1)
+                        .and_then(|e| e.parse().ok())
+                        .ok_or_else(invalid)?;
+                }
+                Some(&"neighborhood") => {
+                    world.rules.neighborhood = words
+                        .get(1)
+                        .and_then(|e| Neighborhood::from_id(e))
+                        .ok_or_else(invalid)?;
+                }
+                Some(&"first_click") => {
+                    first_click = words.get(1).and_then(|e| FirstClick::from_id(e));
+                    world.rules.first_click = first_click.ok_or_else(invalid)?;
+                }
+                Some(&"time") => {
+                    elapsed = Some(
+                        words
+                            .get(1)
+                            .and_then(|e| e.parse().ok())
+                            .map(Duration::from_millis)
+                            .ok_or_else(invalid)?,
+                    );
+                }
+                Some(&"first") => world.first = Some(Pos::new(number(1)?, number(2)?)),
+                Some(&"chunk") => {
+                    let chunk = Pos::new(number(1)?, number(2)?);
+                    let cells = words.get(3).ok_or_else(invalid)?;
+                    if cells.len() != CHUNK_TILES {
+                        return Err(invalid());
+                    }
+                    let mut tiles = world.generate(chunk);
+                    for (i, c) in cells.chars().enumerate() {
+                        let index = i32::try_from(i).unwrap();
+                        let pos = Pos::new(
+                            chunk.row * CHUNK + index / CHUNK,
+                            chunk.col * CHUNK + index % CHUNK,
+                        );
+                        match c {
+                            '#' if !tiles[i].is_mine() => revealed.push(pos),
+                            'F' => {
+                                tiles[i].set_flags(1);
+                                world.flags += 1;
+                            }
+                            '.' => (),
+                            _ => return Err(invalid()),
+                        }
+                    }
+                    world.chunks.insert(chunk, tiles);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+
+        // Every save has the policy and the time, so one without them is broken.
+        let (Some(_), Some(elapsed)) = (first_click, elapsed) else {
+            return Err(invalid());
+        };
+
+        // Numbers are worked out once every saved chunk is back in place.
+        for pos in revealed {
+            let value = world.count(pos);
+            let tile = world.tile_mut(pos);
+            tile.set_value(value);
+            tile.mine();
+            world.revealed += 1;
+            world.zeros += u32::from(value == 0);
+        }
+        Ok((world, elapsed))
+    }
+
+    /// There is no way to win, the game goes on until a mine is hit.
+    pub fn check_state(&self) -> Stage {
+        if self.lost {
+            Stage::Lose
+        } else {
+            Stage::Playing
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(first_click: FirstClick) -> World {
+        World::new(
+            7,
+            Rules {
+                first_click,
+                ..Rules::default()
+            },
+        )
+    }
+
+    /// The tiles within two of `first` that hold a mine.
+    fn mines_near(world: &World, first: Pos) -> Vec<Pos> {
+        (-2..=2)
+            .flat_map(|row| (-2..=2).map(move |col| Pos::new(first.row + row, first.col + col)))
+            .filter(|&e| world.get(e).unwrap().is_mine())
+            .collect()
+    }
+
+    #[test]
+    fn first_click_follows_the_rules() {
+        let first = Pos::new(3, 3);
+        let mut safe = world(FirstClick::Safe3x3);
+        safe.open(first);
+        assert!(mines_near(&safe, first)
+            .iter()
+            .all(|e| (e.row - 3).abs() > 1 || (e.col - 3).abs() > 1));
+        assert!(safe.check_state() == Stage::Playing);
+
+        let mut classic = world(FirstClick::Classic);
+        classic.open(first);
+        assert!(!classic.get(first).unwrap().is_mine());
+
+        assert!(!classic.protected(first, Pos::new(3, 4)));
+        assert!(!world(FirstClick::None).protected(first, first));
+    }
+
+    #[test]
+    fn saves_round_trip() {
+        let mut world = world(FirstClick::Zero);
+        world.open(Pos::new(5, 5));
+        world.flag(Pos::new(40, 40));
+        let text = world.save(Duration::from_millis(61_500));
+
+        let (loaded, elapsed) = World::load(&text).unwrap();
+        assert_eq!(elapsed, Duration::from_millis(61_500));
+        assert_eq!(loaded.rules.first_click, FirstClick::Zero);
+        assert_eq!(loaded.first(), Some(Pos::new(5, 5)));
+        assert_eq!(loaded.revealed, world.revealed);
+        assert_eq!(loaded.zeros, world.zeros);
+        assert_eq!(loaded.flags, 1);
+        assert_eq!(loaded.save(elapsed), text);
+    }
+
+    #[test]
+    fn flags_wait_for_the_first_click() {
+        for seed in 0..50 {
+            let mut world = World::new(seed, Rules::default());
+            let first = Pos::new(3, 3);
+            world.flag(Pos::new(5, 5));
+            assert_eq!(world.flags, 0);
+            assert!(world.chunk(Pos::new(0, 0)).is_none());
+            world.open(first);
+            assert!(world.check_state() == Stage::Playing);
+            assert!(world.get(first).unwrap().is_mined());
+        }
+    }
+
+    #[test]
+    fn rejects_broken_saves() {
+        for text in [
+            "seed x\n",
+            "time -1\n",
+            "first_click corner\n",
+            "chunk 0 0 ##\n",
+            "seed 3\nneighborhood standard\n",
+            "seed 3\nneighborhood standard\nfirst_click zero\n",
+            "seed 3\nneighborhood standard\ntime 10\n",
+        ] {
+            assert!(World::load(text).is_err(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::{fmt::Write, fs, time::Duration};
+
+use crate::{date::Date, game::stats::Stats};
+
+/// Where every finished game is kept between runs.
+pub const HISTORY_PATH: &str = "history.txt";
+/// Where the history is written as a spreadsheet.
+pub const EXPORT_PATH: &str = "history.csv";
+/// How many bars the histogram of completion times has.
+pub const BUCKETS: usize = 10;
+
+/// One finished game, ranked or not.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Game {
+    /// The difficulty that was played, e.g. `small` or `daily`, followed by
+    /// what set the game apart from a standard one, e.g. `small+hex+lives3`.
+    pub mode: String,
+    pub seed: u64,
+    pub date: Date,
+    pub won: bool,
+    /// Set when undo was used, which keeps the time out of the records.
+    pub unranked: bool,
+    pub stats: Stats,
+}
+
+impl Game {
+    fn parse(line: &str) -> Option<Game> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let [mode, seed, date, result, ref stats @ ..] = words[..] else {
+            return None;
+        };
+        // Unranked games say so after the result.
+        let (unranked, stats) = match stats {
+            ["unranked", stats @ ..] => (true, stats),
+            stats => (false, stats),
+        };
+        Some(Game {
+            mode: mode.to_string(),
+            seed: seed.parse().ok()?,
+            date: Date::parse(date)?,
+            won: match result {
+                "won" => true,
+                "lost" => false,
+                _ => return None,
+            },
+            unranked,
+            stats: Stats::parse(stats)?,
+        })
+    }
+}
+
+/// The lifetime record of one difficulty.
+#[derive(Default)]
+pub struct Summary {
+    pub played: u32,
+    pub won: u32,
+    /// Wins since the last loss.
+    pub streak: u32,
+    pub best_streak: u32,
+    /// The times only come from wins without undo.
+    pub best_time: Option<Duration>,
+    pub mean_time: Option<Duration>,
+    /// Wins without undo by completion time, each bar covering `bucket` more than the
+    /// one before it.
+    pub histogram: [u32; BUCKETS],
+    pub bucket: Duration,
+}
+
+impl Summary {
+    fn new<'a>(games: impl Iterator<Item = &'a Game>) -> Summary {
+        let mut summary = Summary::default();
+        let mut times = vec![];
+        for game in games {
+            summary.played += 1;
+            if game.won {
+                summary.won += 1;
+                summary.streak += 1;
+                summary.best_streak = summary.best_streak.max(summary.streak);
+                if !game.unranked {
+                    times.push(game.stats.time);
+                }
+            } else {
+                summary.streak = 0;
+            }
+        }
+        summary.best_time = times.iter().min().copied();
+        summary.mean_time = (!times.is_empty())
+            .then(|| times.iter().sum::<Duration>() / u32::try_from(times.len()).unwrap());
+
+        // The slowest win ends up in the last bar.
+        let slowest = times.iter().max().copied().unwrap_or_default();
+        summary.bucket = (slowest / u32::try_from(BUCKETS).unwrap()).max(Duration::from_secs(1));
+        for time in times {
+            let i = usize::try_from(time.as_millis() / summary.bucket.as_millis()).unwrap();
+            summary.histogram[i.min(BUCKETS - 1)] += 1;
+        }
+        summary
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 {
+            0.0
+        } else {
+            f64::from(self.won) / f64::from(self.played)
+        }
+    }
+}
+
+/// Every game that was played to the end, kept in a small text file with one
+/// line per game. Endless and time attack games are left out: they have no
+/// single board to take the statistics of, and can't be won.
+#[derive(Default)]
+pub struct History {
+    pub games: Vec<Game>,
+}
+
+impl History {
+    /// Reads the saved games. A missing file is an empty history, and lines
+    /// that can not be read are skipped.
+    pub fn load() -> History {
+        let text = fs::read_to_string(HISTORY_PATH).unwrap_or_default();
+        History {
+            games: text.lines().filter_map(Game::parse).collect(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut out = String::new();
+        for e in &self.games {
+            writeln!(
+                out,
+                "{} {} {} {}{} {}",
+                e.mode,
+                e.seed,
+                e.date,
+                if e.won { "won" } else { "lost" },
+                if e.unranked { " unranked" } else { "" },
+                e.stats
+            )
+            .unwrap();
+        }
+        fs::write(HISTORY_PATH, out).map_err(|e| e.to_string())
+    }
+
+    pub fn record(&mut self, game: Game) {
+        self.games.push(game);
+    }
+
+    /// The record of `mode`, in the order its games were played.
+    pub fn summary(&self, mode: &str) -> Summary {
+        Summary::new(self.games.iter().filter(|e| e.mode == mode))
+    }
+
+    /// The history as comma separated values, with a header row.
+    pub fn csv(&self) -> String {
+        let mut out =
+            "mode,seed,date,result,unranked,time_ms,3bv,solved_3bv,left_clicks,right_clicks,\
+                       chord_clicks,effective_clicks,openings,islands,3bv_per_second,\
+                       efficiency,ioe\n"
+                .to_string();
+        for e in &self.games {
+            let stats = &e.stats;
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{:.3},{:.3},{:.3}",
+                e.mode,
+                e.seed,
+                e.date,
+                if e.won { "won" } else { "lost" },
+                e.unranked,
+                stats.time.as_millis(),
+                stats.three_bv,
+                stats.solved,
+                stats.clicks.left,
+                stats.clicks.right,
+                stats.clicks.chord,
+                stats.clicks.effective,
+                stats.openings,
+                stats.islands,
+                stats.three_bv_per_second(),
+                stats.efficiency(),
+                stats.ioe()
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    pub fn export(&self) -> Result<(), String> {
+        fs::write(EXPORT_PATH, self.csv()).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(won: bool, unranked: bool, secs: u64) -> Game {
+        Game {
+            mode: "small".to_string(),
+            seed: 42,
+            date: Date::parse("2026-03-07").unwrap(),
+            won,
+            unranked,
+            stats: Stats {
+                time: Duration::from_secs(secs),
+                ..Stats::default()
+            },
+        }
+    }
+
+    #[test]
+    fn parses_saved_lines() {
+        let game = Game::parse("small 42 2026-03-07 won 12345 10 10 12 3 1 14 2 1").unwrap();
+        assert!(game.won && !game.unranked);
+        assert_eq!(game.seed, 42);
+        assert_eq!(game.stats.time, Duration::from_millis(12_345));
+
+        let game =
+            Game::parse("small 42 2026-03-07 won unranked 12345 10 10 12 3 1 14 2 1").unwrap();
+        assert!(game.won && game.unranked);
+
+        for line in [
+            "small 42 2026-03-07 won",
+            "small x 2026-03-07 won 12345 10 10 12 3 1 14 2 1",
+            "small 42 2026-03-07 won ranked 12345 10 10 12 3 1 14 2 1",
+        ] {
+            assert_eq!(Game::parse(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn sums_up_a_mode() {
+        let history = History {
+            games: vec![
+                game(true, false, 30),
+                game(true, true, 5),
+                game(false, false, 10),
+                game(true, false, 50),
+                game(true, false, 130),
+            ],
+        };
+        let summary = history.summary("small");
+        assert_eq!(summary.played, 5);
+        assert_eq!(summary.won, 4);
+        assert_eq!(summary.streak, 2);
+        assert_eq!(summary.best_streak, 2);
+        // The win with undo is left out of the times.
+        assert_eq!(summary.best_time, Some(Duration::from_secs(30)));
+        assert_eq!(summary.mean_time, Some(Duration::from_secs(70)));
+        assert_eq!(summary.bucket, Duration::from_secs(13));
+        assert_eq!(summary.histogram.iter().sum::<u32>(), 3);
+        assert_eq!(summary.histogram[BUCKETS - 1], 1);
+        assert!((summary.win_rate() - 0.8).abs() < 1e-9);
+
+        let empty = history.summary("large");
+        assert_eq!(empty.played, 0);
+        assert_eq!(empty.mean_time, None);
+        assert!(empty.win_rate().abs() < 1e-9);
+    }
+
+    #[test]
+    fn writes_a_spreadsheet() {
+        let history = History {
+            games: vec![game(true, true, 12)],
+        };
+        let csv = history.csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("mode,seed,date,result,unranked,time_ms,"));
+        assert!(lines[1].starts_with("small,42,2026-03-07,won,true,12000,"));
+        assert_eq!(lines[0].split(',').count(), lines[1].split(',').count());
+    }
+}
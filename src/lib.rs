@@ -0,0 +1,16 @@
+#![warn(clippy::pedantic)]
+#![allow(
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::must_use_candidate
+)]
+
+pub mod agent;
+pub mod audio;
+pub mod context;
+pub mod date;
+pub mod game;
+pub mod history;
+pub mod scores;
+pub mod stopwatch;
+pub mod ui;
@@ -1,33 +1,169 @@
 #![warn(clippy::pedantic)]
 
-use std::time::Duration;
+use std::{
+    env,
+    path::Path,
+    time::{Duration, Instant},
+};
 
-use context::Context;
-use game::{
-    map::{Map, Size},
-    tile::TILE_SIZE,
-    Stage,
+use minesweeper::{
+    audio::{Audio, Snapshot},
+    context::Context,
+    date::Date,
+    game::{
+        board::Size,
+        endless::{Endless, VIEW_HEIGHT, VIEW_WIDTH},
+        layout::Layout,
+        map::Map,
+        puzzle::Progress,
+        replay::{Click, Move, Replay},
+        rules::Rules,
+        time_attack::TimeAttack,
+        Stage,
+    },
+    history::{Game, History},
+    scores::{Score, Scores, DAILY},
+    stopwatch::Stopwatch,
+    ui::{
+        end_menu::{self, end_menu, Playfield},
+        main_menu::{self, main_menu, Settings},
+        pause_menu::{self, pause_menu},
+    },
 };
+use resource::Resource;
 use sdl2::{
-    event::Event, keyboard::Scancode, mouse::MouseButton, rwops::RWops, video::WindowPos, EventPump,
-};
-use ui::{
-    end_menu::{self, end_menu},
-    main_menu::{self, main_menu},
+    event::{Event, WindowEvent},
+    keyboard::{Keycode, Mod, Scancode},
+    mouse::MouseButton,
+    render::{TextureCreator, WindowCanvas},
+    rwops::RWops,
+    ttf::{Font, Sdl2TtfContext},
+    video::{WindowContext, WindowPos},
+    EventPump,
 };
 
-mod context;
-mod game;
-mod stopwatch;
-mod ui;
+const SCROLL_SPEED: i32 = 8;
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Classic(Size),
+    Countdown(Size),
+    Daily,
+    Endless,
+    TimeAttack,
+    /// The board loaded from the command line or a code.
+    Fixed,
+    /// Watching the replay loaded from the command line.
+    Watch,
+    /// The puzzle picked from the puzzle list.
+    Puzzle,
+}
+
+impl Mode {
+    fn new(status: main_menu::ClickStatus, countdown: bool) -> Mode {
+        let size = match status {
+            main_menu::ClickStatus::Small => Size::Small,
+            main_menu::ClickStatus::Normal => Size::Normal,
+            main_menu::ClickStatus::Large => Size::Large,
+            main_menu::ClickStatus::Endless => return Mode::Endless,
+            main_menu::ClickStatus::Daily => return Mode::Daily,
+            main_menu::ClickStatus::TimeAttack => return Mode::TimeAttack,
+            main_menu::ClickStatus::PlayCode => return Mode::Fixed,
+            main_menu::ClickStatus::Puzzles => return Mode::Puzzle,
+            main_menu::ClickStatus::FirstClick
+            | main_menu::ClickStatus::Topology
+            | main_menu::ClickStatus::Wrap
+            | main_menu::ClickStatus::Neighborhood
+            | main_menu::ClickStatus::Shape
+            | main_menu::ClickStatus::MultiMine
+            | main_menu::ClickStatus::Lives
+            | main_menu::ClickStatus::Clock
+            | main_menu::ClickStatus::Practice
+            | main_menu::ClickStatus::Volume
+            | main_menu::ClickStatus::Sound
+            | main_menu::ClickStatus::Stats => unreachable!(),
+        };
+        Mode::Classic(size).clocked(countdown)
+    }
+
+    /// The same game, played against the clock if `countdown` is set.
+    fn clocked(self, countdown: bool) -> Mode {
+        match self {
+            Mode::Classic(size) | Mode::Countdown(size) if countdown => Mode::Countdown(size),
+            Mode::Classic(size) | Mode::Countdown(size) => Mode::Classic(size),
+            mode => mode,
+        }
+    }
+}
 
 enum RunStatus {
     Menu,
     Exit,
-    PlayAgain(Size),
+    PlayAgain(Mode),
+}
+
+/// Something that interrupts a game.
+enum Action {
+    Quit,
+    Pause,
+}
+
+/// How a game was left.
+enum Outcome {
+    Over(Stage),
+    Restart,
+    Menu,
+    Exit,
+}
+
+/// The parts of the context a game needs besides the texture creator, which
+/// stays borrowed by the game's textures for as long as it lasts.
+struct Screen<'c> {
+    canvas: &'c mut WindowCanvas,
+    event_pump: &'c mut EventPump,
+    ttf: &'c Sdl2TtfContext,
+    font_res: &'c Resource<[u8]>,
+    audio: &'c Audio,
 }
 
-fn handle_events(event_pump: &mut EventPump, map: &mut Map) -> bool {
+/// Ctrl+Z undoes, and Ctrl+Y or Ctrl+Shift+Z redo.
+fn undo_event(e: &Event, map: &mut Map) -> bool {
+    let Event::KeyDown {
+        keycode: Some(key),
+        keymod,
+        ..
+    } = *e
+    else {
+        return false;
+    };
+    if !keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) {
+        return false;
+    }
+    let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+    match key {
+        Keycode::Z if shift => map.redo(),
+        Keycode::Z => map.undo(),
+        Keycode::Y => map.redo(),
+        _ => return false,
+    }
+    true
+}
+
+/// The pause key, and losing focus or being minimized, pause the game.
+fn pause_event(e: &Event) -> bool {
+    matches!(
+        e,
+        Event::KeyDown {
+            keycode: Some(Keycode::P | Keycode::Escape),
+            ..
+        } | Event::Window {
+            win_event: WindowEvent::FocusLost | WindowEvent::Minimized,
+            ..
+        }
+    )
+}
+
+fn handle_events(event_pump: &mut EventPump, map: &mut Map) -> Option<Action> {
     let shift_held = event_pump
         .keyboard_state()
         .is_scancode_pressed(Scancode::LShift)
@@ -37,7 +173,9 @@ fn handle_events(event_pump: &mut EventPump, map: &mut Map) -> bool {
 
     for e in event_pump.poll_iter() {
         match e {
-            Event::Quit { .. } => return true,
+            Event::Quit { .. } => return Some(Action::Quit),
+            _ if pause_event(&e) => return Some(Action::Pause),
+            _ if undo_event(&e, map) => (),
             Event::MouseButtonDown {
                 mouse_btn, x, y, ..
             } => match mouse_btn {
@@ -46,13 +184,7 @@ fn handle_events(event_pump: &mut EventPump, map: &mut Map) -> bool {
                         if shift_held {
                             map.flag(tile);
                         } else {
-                            if map.first_move {
-                                map.generate_mines(&mut rand::thread_rng(), tile.0, tile.1);
-                                map.generate_tiles();
-                                map.stopwatch.start();
-                                map.first_move = false;
-                            }
-                            map.mine(tile, &mut Vec::new());
+                            map.mine(tile);
                         }
                     }
                 }
@@ -66,90 +198,537 @@ fn handle_events(event_pump: &mut EventPump, map: &mut Map) -> bool {
             _ => (),
         }
     }
-    false
+    None
 }
 
-fn run(again: Option<Size>) -> Result<RunStatus, String> {
-    let mut ctx = Context::new()?;
-    let font = ctx
-        .ttf
-        .load_font_from_rwops(RWops::from_bytes(&ctx.font_res)?, 15)?;
-    let mut map;
-    if let Some(size) = again {
-        map = Map::new(size, &ctx.tex_creator, &font);
-    } else if let Some(status) = main_menu(
-        &ctx.tex_creator,
-        &ctx.ttf,
-        &mut ctx.event_pump,
-        &mut ctx.canvas,
-        &ctx.font_res,
-    )? {
-        map = Map::new(
-            match status {
-                main_menu::ClickStatus::Small => Size::Small,
-                main_menu::ClickStatus::Normal => Size::Normal,
-                main_menu::ClickStatus::Large => Size::Large,
+/// Arrow keys or WASD scroll the endless world, and so does dragging it with
+/// the middle mouse button.
+fn handle_endless_events(event_pump: &mut EventPump, endless: &mut Endless) -> Option<Action> {
+    let keys = event_pump.keyboard_state();
+    let held = |a, b| keys.is_scancode_pressed(a) || keys.is_scancode_pressed(b);
+    let shift_held = held(Scancode::LShift, Scancode::RShift);
+    let dx = i32::from(held(Scancode::Right, Scancode::D))
+        - i32::from(held(Scancode::Left, Scancode::A));
+    let dy =
+        i32::from(held(Scancode::Down, Scancode::S)) - i32::from(held(Scancode::Up, Scancode::W));
+    endless.scroll(dx * SCROLL_SPEED, dy * SCROLL_SPEED);
+
+    for e in event_pump.poll_iter() {
+        match e {
+            Event::Quit { .. } => return Some(Action::Quit),
+            _ if pause_event(&e) => return Some(Action::Pause),
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => match mouse_btn {
+                MouseButton::Left => {
+                    if let Some(tile) = endless.inside(x, y) {
+                        if shift_held {
+                            endless.flag(tile);
+                        } else {
+                            endless.mine(tile);
+                        }
+                    }
+                }
+                MouseButton::Right => {
+                    if let Some(tile) = endless.inside(x, y) {
+                        endless.flag(tile);
+                    }
+                }
+                _ => (),
             },
-            &ctx.tex_creator,
-            &font,
-        );
-    } else {
-        return Ok(RunStatus::Exit);
+            Event::MouseMotion {
+                mousestate,
+                xrel,
+                yrel,
+                ..
+            } if mousestate.middle() => endless.scroll(-xrel, -yrel),
+            _ => (),
+        }
     }
+    None
+}
+
+fn score(mode: &str, date: Date, map: &Map, won: bool) -> Score {
+    Score {
+        mode: mode.to_string(),
+        date,
+        won,
+        stats: map.stats(),
+    }
+}
 
-    let win = ctx.canvas.window_mut();
+fn fit_window(canvas: &mut WindowCanvas, width: i32, height: i32) -> Result<(), String> {
+    let win = canvas.window_mut();
     win.set_size(
-        u32::try_from(map.dim.1 * TILE_SIZE).unwrap(),
-        u32::try_from(map.dim.0 * TILE_SIZE).unwrap(),
+        u32::try_from(width).unwrap(),
+        u32::try_from(height).unwrap(),
     )
     .map_err(|e| e.to_string())?;
     win.set_position(WindowPos::Centered, WindowPos::Centered);
+    Ok(())
+}
+
+/// Stops the clock and shows the pause menu. Returns `None` once the game is
+/// resumed, or how it was left otherwise.
+fn pause(
+    screen: &mut Screen,
+    stopwatch: &mut Stopwatch,
+    (width, height): (i32, i32),
+    tex_creator: &TextureCreator<WindowContext>,
+    settings: &mut Settings,
+) -> Result<Option<Outcome>, String> {
+    // A game that has not started yet should not start on resume either.
+    let running = stopwatch.is_running();
+    stopwatch.pause();
+    let status = pause_menu(
+        tex_creator,
+        screen.ttf,
+        screen.event_pump,
+        screen.canvas,
+        screen.font_res,
+        screen.audio,
+        (width, height),
+        settings,
+    )?;
+    Ok(match status {
+        None => Some(Outcome::Exit),
+        Some(pause_menu::ClickStatus::Restart) => Some(Outcome::Restart),
+        Some(pause_menu::ClickStatus::Menu) => Some(Outcome::Menu),
+        Some(pause_menu::ClickStatus::Resume) => {
+            fit_window(screen.canvas, width, height)?;
+            if running {
+                stopwatch.resume();
+            }
+            None
+        }
+        Some(pause_menu::ClickStatus::Settings) => unreachable!(),
+    })
+}
 
-    let mut state;
-    'gameloop: loop {
-        if handle_events(&mut ctx.event_pump, &mut map) {
-            return Ok(RunStatus::Exit);
+/// Plays until the game is over or left from the pause menu.
+fn play_map<'a>(
+    map: &mut Map<'a>,
+    screen: &mut Screen,
+    font: &Font,
+    tex_creator: &'a TextureCreator<WindowContext>,
+    settings: &mut Settings,
+) -> Result<Outcome, String> {
+    loop {
+        let before = Snapshot::of(&map.board);
+        match handle_events(screen.event_pump, map) {
+            Some(Action::Quit) => return Ok(Outcome::Exit),
+            Some(Action::Pause) => {
+                let size = (map.width(), map.height());
+                if let Some(outcome) =
+                    pause(screen, &mut map.stopwatch, size, tex_creator, settings)?
+                {
+                    return Ok(outcome);
+                }
+            }
+            None => (),
         }
+        screen.audio.play_change(before, Snapshot::of(&map.board));
 
-        state = map.check_state();
-        match state {
-            Stage::Win | Stage::Lose => break 'gameloop,
+        let state = map.check_state();
+        if state != Stage::Playing {
+            map.stopwatch.stop();
+            return Ok(Outcome::Over(state));
+        }
+
+        screen.canvas.clear();
+        map.render(screen.canvas, font, tex_creator, None)?;
+        screen.canvas.present();
+
+        std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
+    }
+}
+
+fn new_map<'a>(
+    mode: Mode,
+    settings: &Settings,
+    tex_creator: &'a TextureCreator<WindowContext>,
+    font: &'a Font,
+) -> Map<'a> {
+    let rules = settings.rules;
+    let mut map = match mode {
+        Mode::Classic(size) => Map::new(size, rules, settings.mask.as_ref(), tex_creator, font),
+        Mode::Countdown(size) => {
+            let mut map = Map::new(size, rules, settings.mask.as_ref(), tex_creator, font);
+            map.stopwatch = Stopwatch::countdown(size.time_limit());
+            map
+        }
+        Mode::Daily => Map::daily(Date::today(), tex_creator, font),
+        Mode::Fixed => Map::fixed(settings.layout.as_ref().unwrap(), rules, tex_creator, font),
+        Mode::Puzzle => Map::puzzle(settings.puzzle.as_ref().unwrap(), tex_creator, font),
+        Mode::Watch => {
+            // A replay plays out under the rules it was recorded with, never
+            // the ones picked in the menu.
+            let layout = &settings.replay.as_ref().unwrap().layout;
+            Map::fixed(layout, layout.rules.unwrap_or_default(), tex_creator, font)
+        }
+        Mode::Endless | Mode::TimeAttack => unreachable!(),
+    };
+    // Everyone plays the same daily board, so it is always played for real.
+    map.board.safe_death = settings.practice && !matches!(mode, Mode::Watch | Mode::Daily);
+    map
+}
+
+/// What a game goes by in the history: the board, followed by everything
+/// that sets it apart from a standard game, e.g. `small+hex+practice`. The
+/// statistics only sum up standard games, so variants never mix with them.
+fn history_mode(mode: Mode, map: &Map, settings: &Settings) -> String {
+    let name = match mode {
+        // Every daily and puzzle board is played the same way.
+        Mode::Daily => return DAILY.to_string(),
+        Mode::Puzzle => return "puzzle".to_string(),
+        Mode::Fixed => "custom".to_string(),
+        _ => map.size.to_string().to_lowercase(),
+    };
+    let mut tags = map.board.rules.tags();
+    if matches!(mode, Mode::Countdown(_)) {
+        tags.push("countdown".to_string());
+    }
+    if settings.mask.is_some() && !matches!(mode, Mode::Fixed) {
+        tags.push("mask".to_string());
+    }
+    if map.board.safe_death {
+        tags.push("practice".to_string());
+    }
+    std::iter::once(name)
+        .chain(tags)
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Plays a board like `play_map` and keeps every finished game in the
+/// history. The result also goes on the scores if it counts: the first daily
+/// game of the day, and wins on classic boards with the default settings.
+/// Games that used undo are kept in the history as unranked, and never go on
+/// the scores as a win.
+fn play_scored<'a>(
+    mode: Mode,
+    map: &mut Map<'a>,
+    screen: &mut Screen,
+    font: &Font,
+    tex_creator: &'a TextureCreator<WindowContext>,
+    settings: &mut Settings,
+) -> Result<Outcome, String> {
+    let today = Date::today();
+    let mut scores = Scores::load();
+    // Only the first daily game of the day counts. It is entered as lost
+    // right away, so quitting can't be used to retry.
+    let official = matches!(mode, Mode::Daily) && scores.daily(today).is_none();
+    let ranked = matches!(mode, Mode::Classic(_))
+        && settings.rules == Rules::default()
+        && settings.mask.is_none()
+        && !settings.practice;
+    if official {
+        scores.record(score(DAILY, today, map, false));
+        scores.save()?;
+    }
+
+    let outcome = play_map(map, screen, font, tex_creator, settings)?;
+    let Outcome::Over(state) = &outcome else {
+        return Ok(outcome);
+    };
+    let won = *state == Stage::Win;
+    let mut history = History::load();
+    history.record(Game {
+        mode: history_mode(mode, map, settings),
+        seed: map.seed,
+        date: today,
+        won,
+        unranked: map.undone,
+        stats: map.stats(),
+    });
+    history.save()?;
+    // Replays start from a blank board, which puzzles don't, and go on until
+    // the first mine, which practice games don't.
+    let replay = map.replay();
+    if !map.undone && !map.board.safe_death && replay.is_classic() && !matches!(mode, Mode::Puzzle)
+    {
+        replay.save(today)?;
+    }
+    if won && matches!(mode, Mode::Puzzle) {
+        Progress::load().solve(settings.puzzle.as_ref().unwrap())?;
+    }
+    let won = won && !map.undone;
+    if official {
+        scores.record(score(DAILY, today, map, won));
+        scores.save()?;
+    } else if ranked && won {
+        let name = map.size.to_string().to_lowercase();
+        scores.record(score(&name, today, map, won));
+        scores.save()?;
+    }
+    Ok(outcome)
+}
+
+/// Plays back the moves of a replay as fast as they were made. Escape goes
+/// back to the menu, and the board stays up if the replay stops before the
+/// game is over.
+fn watch<'a>(
+    moves: &[Move],
+    map: &mut Map<'a>,
+    screen: &mut Screen,
+    font: &Font,
+    tex_creator: &'a TextureCreator<WindowContext>,
+) -> Result<Outcome, String> {
+    let start = Instant::now();
+    let mut moves = moves.iter().peekable();
+    loop {
+        for e in screen.event_pump.poll_iter() {
+            match e {
+                Event::Quit { .. } => return Ok(Outcome::Exit),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => return Ok(Outcome::Menu),
+                _ => (),
+            }
+        }
+
+        let before = Snapshot::of(&map.board);
+        while let Some(e) = moves.next_if(|e| e.time <= start.elapsed()) {
+            match e.click {
+                Click::Flag => map.flag(e.pos),
+                Click::Reveal | Click::Chord => map.mine(e.pos),
+            }
+        }
+        screen.audio.play_change(before, Snapshot::of(&map.board));
+        let state = map.check_state();
+        if state != Stage::Playing {
+            map.stopwatch.stop();
+            return Ok(Outcome::Over(state));
+        }
+
+        screen.canvas.clear();
+        map.render(screen.canvas, font, tex_creator, None)?;
+        screen.canvas.present();
+
+        std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
+    }
+}
+
+/// Deals boards until the time budget runs out.
+fn play_time_attack<'a>(
+    run: &mut TimeAttack<'a>,
+    screen: &mut Screen,
+    font: &'a Font,
+    tex_creator: &'a TextureCreator<WindowContext>,
+    settings: &mut Settings,
+) -> Result<Outcome, String> {
+    loop {
+        let before = Snapshot::of(&run.map.board);
+        match handle_events(screen.event_pump, &mut run.map) {
+            Some(Action::Quit) => return Ok(Outcome::Exit),
+            Some(Action::Pause) => {
+                let size = (run.width(), run.height());
+                if let Some(outcome) =
+                    pause(screen, &mut run.map.stopwatch, size, tex_creator, settings)?
+                {
+                    return Ok(outcome);
+                }
+            }
+            None => (),
+        }
+        screen
+            .audio
+            .play_change(before, Snapshot::of(&run.map.board));
+
+        match run.map.check_state() {
             Stage::Playing => (),
+            Stage::TimeOut => {
+                run.map.stopwatch.stop();
+                return Ok(Outcome::Over(Stage::TimeOut));
+            }
+            Stage::Win | Stage::Lose => run.next_board(tex_creator, font),
         }
 
-        ctx.canvas.clear();
-        map.render(&mut ctx.canvas, &font, &ctx.tex_creator, None)?;
-        ctx.canvas.present();
+        screen.canvas.clear();
+        run.render(screen.canvas, font, tex_creator, None)?;
+        screen.canvas.present();
 
         std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
     }
+}
 
-    map.stopwatch.stop();
-    if let Some(status) = end_menu(
-        &state,
+/// Plays until a mine is hit. A game that is left unfinished is saved for
+/// later, unless it is restarted.
+fn play_endless<'a>(
+    endless: &mut Endless<'a>,
+    screen: &mut Screen,
+    font: &Font,
+    tex_creator: &'a TextureCreator<WindowContext>,
+    settings: &mut Settings,
+) -> Result<Outcome, String> {
+    while endless.world.check_state() == Stage::Playing {
+        let before = Snapshot::of_world(&endless.world);
+        let outcome = match handle_endless_events(screen.event_pump, endless) {
+            Some(Action::Quit) => Some(Outcome::Exit),
+            Some(Action::Pause) => pause(
+                screen,
+                &mut endless.stopwatch,
+                (VIEW_WIDTH, VIEW_HEIGHT),
+                tex_creator,
+                settings,
+            )?,
+            None => None,
+        };
+        screen
+            .audio
+            .play_change(before, Snapshot::of_world(&endless.world));
+        match outcome {
+            Some(Outcome::Restart) => {
+                Endless::discard_save()?;
+                return Ok(Outcome::Restart);
+            }
+            Some(outcome) => {
+                endless.save()?;
+                return Ok(outcome);
+            }
+            None => (),
+        }
+
+        screen.canvas.clear();
+        endless.render(screen.canvas, font, tex_creator, None)?;
+        screen.canvas.present();
+
+        std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
+    }
+
+    endless.stopwatch.stop();
+    Endless::discard_save()?;
+    Ok(Outcome::Over(Stage::Lose))
+}
+
+fn run(again: Option<Mode>, settings: &mut Settings) -> Result<RunStatus, String> {
+    let mut ctx = Context::new(settings.loudness())?;
+    let font = ctx
+        .ttf
+        .load_font_from_rwops(RWops::from_bytes(&ctx.font_res)?, 15)?;
+    let mode = if let Some(mode) = again {
+        // The clock can be changed from the pause menu.
+        mode.clocked(settings.countdown)
+    } else if let Some(status) = main_menu(
         &ctx.tex_creator,
         &ctx.ttf,
         &mut ctx.event_pump,
         &mut ctx.canvas,
         &ctx.font_res,
-        &mut map,
+        &ctx.audio,
+        settings,
+    )? {
+        Mode::new(status, settings.countdown)
+    } else {
+        return Ok(RunStatus::Exit);
+    };
+
+    let mut screen = Screen {
+        canvas: &mut ctx.canvas,
+        event_pump: &mut ctx.event_pump,
+        ttf: &ctx.ttf,
+        font_res: &ctx.font_res,
+        audio: &ctx.audio,
+    };
+    let mut map;
+    let mut endless;
+    let mut time_attack;
+    let (outcome, field): (Outcome, &mut dyn Playfield) = match mode {
+        Mode::Classic(_) | Mode::Countdown(_) | Mode::Daily | Mode::Fixed | Mode::Puzzle => {
+            map = new_map(mode, settings, &ctx.tex_creator, &font);
+            fit_window(screen.canvas, map.width(), map.height())?;
+            let outcome = play_scored(
+                mode,
+                &mut map,
+                &mut screen,
+                &font,
+                &ctx.tex_creator,
+                settings,
+            )?;
+            (outcome, &mut map)
+        }
+        Mode::Watch => {
+            map = new_map(mode, settings, &ctx.tex_creator, &font);
+            fit_window(screen.canvas, map.width(), map.height())?;
+            let moves = &settings.replay.as_ref().unwrap().moves;
+            let outcome = watch(moves, &mut map, &mut screen, &font, &ctx.tex_creator)?;
+            (outcome, &mut map)
+        }
+        Mode::TimeAttack => {
+            time_attack = TimeAttack::new(rand::random(), settings.rules, &ctx.tex_creator, &font);
+            fit_window(screen.canvas, time_attack.width(), time_attack.height())?;
+            let outcome = play_time_attack(
+                &mut time_attack,
+                &mut screen,
+                &font,
+                &ctx.tex_creator,
+                settings,
+            )?;
+            (outcome, &mut time_attack)
+        }
+        Mode::Endless => {
+            endless = Endless::new(settings.rules, &ctx.tex_creator, &font);
+            fit_window(screen.canvas, VIEW_WIDTH, VIEW_HEIGHT)?;
+            let outcome =
+                play_endless(&mut endless, &mut screen, &font, &ctx.tex_creator, settings)?;
+            (outcome, &mut endless)
+        }
+    };
+    let state = match outcome {
+        Outcome::Over(state) => state,
+        Outcome::Restart => return Ok(RunStatus::PlayAgain(mode)),
+        Outcome::Menu => return Ok(RunStatus::Menu),
+        Outcome::Exit => return Ok(RunStatus::Exit),
+    };
+
+    if let Some(status) = end_menu(
+        &state,
+        &ctx.tex_creator,
+        screen.ttf,
+        screen.event_pump,
+        screen.canvas,
+        screen.font_res,
+        screen.audio,
+        field,
     )? {
         match status {
             end_menu::ClickStatus::Menu => return Ok(RunStatus::Menu),
             end_menu::ClickStatus::Exit => return Ok(RunStatus::Exit),
-            end_menu::ClickStatus::PlayAgain => return Ok(RunStatus::PlayAgain(map.size)),
+            end_menu::ClickStatus::PlayAgain => return Ok(RunStatus::PlayAgain(mode)),
+            end_menu::ClickStatus::Export
+            | end_menu::ClickStatus::CopyCode
+            | end_menu::ClickStatus::Share => unreachable!(),
         }
     }
     Ok(RunStatus::Exit)
 }
 
+/// Starts at the main menu, or straight away on the board or replay in the
+/// file given as the only argument.
 fn main() -> Result<(), String> {
-    let mut again_size = None;
+    let mut again = None;
+    let mut settings = Settings::default();
+    if let Some(path) = env::args().nth(1) {
+        let path = Path::new(&path);
+        if Replay::is_replay(path) {
+            settings.replay = Some(Replay::load(path)?);
+            again = Some(Mode::Watch);
+        } else {
+            settings.layout = Some(Layout::load(path)?);
+            again = Some(Mode::Fixed);
+        }
+    }
     loop {
-        match run(again_size)? {
+        match run(again, &mut settings)? {
             RunStatus::Exit => break,
-            RunStatus::Menu => again_size = None,
-            RunStatus::PlayAgain(size) => again_size = Some(size),
+            RunStatus::Menu => {
+                settings.layout = None;
+                settings.replay = None;
+                settings.puzzle = None;
+                again = None;
+            }
+            RunStatus::PlayAgain(mode) => again = Some(mode),
         }
     }
 
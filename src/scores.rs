@@ -0,0 +1,163 @@
+use std::{fmt::Write, fs};
+
+use crate::{date::Date, game::stats::Stats};
+
+/// Where results are kept between runs.
+pub const SCORES_PATH: &str = "scores.txt";
+/// Results of the daily challenge are stored under this mode.
+pub const DAILY: &str = "daily";
+
+/// The result of one game.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Score {
+    /// The board that was played, e.g. `small` or `daily`.
+    pub mode: String,
+    pub date: Date,
+    pub won: bool,
+    pub stats: Stats,
+}
+
+impl Score {
+    fn parse(line: &str) -> Option<Score> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let [mode, date, result, ref stats @ ..] = words[..] else {
+            return None;
+        };
+        Some(Score {
+            mode: mode.to_string(),
+            date: Date::parse(date)?,
+            won: match result {
+                "won" => true,
+                "lost" => false,
+                _ => return None,
+            },
+            stats: Stats::parse(stats)?,
+        })
+    }
+}
+
+/// Every ranked result and daily attempt, kept in a small text file with one
+/// line per game.
+#[derive(Default)]
+pub struct Scores {
+    pub entries: Vec<Score>,
+}
+
+impl Scores {
+    /// Reads the saved results. A missing file is an empty table, and lines
+    /// that can not be read are skipped.
+    pub fn load() -> Scores {
+        let text = fs::read_to_string(SCORES_PATH).unwrap_or_default();
+        Scores {
+            entries: text.lines().filter_map(Score::parse).collect(),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut out = String::new();
+        for e in &self.entries {
+            writeln!(
+                out,
+                "{} {} {} {}",
+                e.mode,
+                e.date,
+                if e.won { "won" } else { "lost" },
+                e.stats
+            )
+            .unwrap();
+        }
+        fs::write(SCORES_PATH, out).map_err(|e| e.to_string())
+    }
+
+    /// Adds a result. There is only one daily attempt per day, so a daily
+    /// result replaces the one already stored for that day.
+    pub fn record(&mut self, score: Score) {
+        if score.mode == DAILY {
+            self.entries
+                .retain(|e| !(e.mode == DAILY && e.date == score.date));
+        }
+        self.entries.push(score);
+    }
+
+    pub fn daily(&self, date: Date) -> Option<&Score> {
+        self.entries
+            .iter()
+            .find(|e| e.mode == DAILY && e.date == date)
+    }
+
+    /// The fastest win in `mode`.
+    pub fn best(&self, mode: &str) -> Option<&Score> {
+        self.entries
+            .iter()
+            .filter(|e| e.mode == mode && e.won)
+            .min_by_key(|e| e.stats.time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn score(mode: &str, day: &str, won: bool, millis: u64) -> Score {
+        Score {
+            mode: mode.to_string(),
+            date: Date::parse(day).unwrap(),
+            won,
+            stats: Stats {
+                time: Duration::from_millis(millis),
+                ..Stats::default()
+            },
+        }
+    }
+
+    #[test]
+    fn parses_saved_lines() {
+        let score = Score::parse("small 2026-03-07 won 12345 10 10 12 3 1 14 2 1").unwrap();
+        assert_eq!(score.mode, "small");
+        assert_eq!(score.date.to_string(), "2026-03-07");
+        assert!(score.won);
+        assert_eq!(score.stats.time, Duration::from_millis(12_345));
+        assert_eq!(score.stats.clicks.total(), 16);
+        assert_eq!(score.stats.islands, 1);
+    }
+
+    #[test]
+    fn skips_lines_it_can_not_read() {
+        for line in [
+            "",
+            "small 2026-03-07 won",
+            "small 2026-03-07 tied 12345 10 10 12 3 1 14 2 1",
+            "small 2026-13-07 won 12345 10 10 12 3 1 14 2 1",
+            "small 2026-03-07 won 12345 10 10 12 3 1 14 2",
+            // Only the time, the 3BV and the clicks.
+            "small 2026-03-07 won 12345 10 12",
+        ] {
+            assert_eq!(Score::parse(line), None, "{line}");
+        }
+    }
+
+    #[test]
+    fn keeps_one_daily_result_a_day() {
+        let mut scores = Scores::default();
+        scores.record(score(DAILY, "2026-03-07", false, 0));
+        scores.record(score(DAILY, "2026-03-08", false, 0));
+        scores.record(score(DAILY, "2026-03-07", true, 9_500));
+        assert_eq!(scores.entries.len(), 2);
+        let today = Date::parse("2026-03-07").unwrap();
+        assert!(scores.daily(today).unwrap().won);
+    }
+
+    #[test]
+    fn best_is_the_fastest_win() {
+        let mut scores = Scores::default();
+        scores.record(score("small", "2026-03-07", true, 9_500));
+        scores.record(score("small", "2026-03-07", false, 1_500));
+        scores.record(score("small", "2026-03-08", true, 7_500));
+        scores.record(score("large", "2026-03-08", true, 5_500));
+        let best = scores.best("small").unwrap();
+        assert_eq!(best.stats.time, Duration::from_millis(7_500));
+        assert_eq!(scores.best("normal"), None);
+    }
+}
@@ -3,6 +3,8 @@ use std::time::{Duration, Instant};
 pub struct Stopwatch {
     start_time: Option<Instant>,
     elapsed: Duration,
+    // Set when counting down from a time limit.
+    limit: Option<Duration>,
 }
 
 impl Default for Stopwatch {
@@ -10,11 +12,29 @@ impl Default for Stopwatch {
         Stopwatch {
             start_time: None,
             elapsed: Duration::from_secs(0),
+            limit: None,
         }
     }
 }
 
 impl Stopwatch {
+    /// A stopwatch that runs out once `limit` has passed.
+    pub fn countdown(limit: Duration) -> Stopwatch {
+        Stopwatch {
+            limit: Some(limit),
+            ..Stopwatch::default()
+        }
+    }
+
+    /// A stopped stopwatch that already shows `elapsed`, for games that are
+    /// continued.
+    pub fn with_elapsed(elapsed: Duration) -> Stopwatch {
+        Stopwatch {
+            elapsed,
+            ..Stopwatch::default()
+        }
+    }
+
     pub fn start(&mut self) {
         self.start_time = Some(Instant::now());
     }
@@ -24,8 +44,87 @@ impl Stopwatch {
         self.start_time = None;
     }
 
+    /// Stops the clock until `resume` is called. Unlike `stop` and `start`,
+    /// pausing twice or resuming a running clock loses no time.
+    pub fn pause(&mut self) {
+        if self.is_running() {
+            self.stop();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if !self.is_running() {
+            self.start();
+        }
+    }
+
+    /// Adds `time` to the clock, whether it is running or not.
+    pub fn penalize(&mut self, time: Duration) {
+        self.elapsed += time;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.start_time.is_some()
+    }
+
     pub fn elapsed(&self) -> Duration {
         self.start_time
             .map_or(self.elapsed, |t| t.elapsed() + self.elapsed)
     }
+
+    /// The time left on a countdown, or `None` if there is no time limit.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.limit.map(|e| e.saturating_sub(self.elapsed()))
+    }
+
+    pub fn expired(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn pausing_stops_the_clock() {
+        let mut stopwatch = Stopwatch::with_elapsed(Duration::from_millis(1500));
+        assert_eq!(stopwatch.elapsed(), Duration::from_millis(1500));
+        stopwatch.start();
+        thread::sleep(Duration::from_millis(5));
+        stopwatch.pause();
+        stopwatch.pause();
+        let paused = stopwatch.elapsed();
+        assert!(paused >= Duration::from_millis(1505));
+
+        thread::sleep(Duration::from_millis(200));
+        assert_eq!(stopwatch.elapsed(), paused);
+        stopwatch.resume();
+        assert!(stopwatch.is_running());
+        assert!(stopwatch.elapsed() >= paused);
+        assert!(stopwatch.elapsed() < paused + Duration::from_millis(200));
+    }
+
+    #[test]
+    fn counts_down_to_zero() {
+        let mut stopwatch = Stopwatch::countdown(Duration::from_secs(90));
+        assert_eq!(stopwatch.remaining(), Some(Duration::from_secs(90)));
+        stopwatch.penalize(Duration::from_secs(7));
+        assert_eq!(stopwatch.remaining(), Some(Duration::from_secs(83)));
+        assert!(!stopwatch.expired());
+
+        stopwatch.start();
+        thread::sleep(Duration::from_millis(5));
+        assert!(stopwatch.remaining().unwrap() <= Duration::from_millis(82_995));
+
+        // Running out leaves nothing rather than wrapping around.
+        stopwatch.penalize(Duration::from_secs(100));
+        assert_eq!(stopwatch.remaining(), Some(Duration::ZERO));
+        assert!(stopwatch.expired());
+
+        assert_eq!(Stopwatch::default().remaining(), None);
+        assert!(!Stopwatch::default().expired());
+    }
 }
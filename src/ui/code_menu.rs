@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use resource::Resource;
+
+use sdl2::{
+    event::Event,
+    keyboard::{Keycode, Mod},
+    mouse::MouseButton,
+    render::{TextureCreator, WindowCanvas},
+    rwops::RWops,
+    ttf::Sdl2TtfContext,
+    video::WindowContext,
+    EventPump,
+};
+
+use crate::{audio::Audio, buttons, game::layout::Layout, texts};
+
+use super::{
+    button::Button,
+    menu::{ClickHandler, Menu},
+    text::Text,
+    POS_CENTERED,
+};
+
+/// How many characters of the code are shown on one line.
+const LINE: usize = 40;
+/// How many lines of the code are shown. Longer codes show their end.
+const LINES: usize = 6;
+
+pub enum ClickStatus {
+    Paste,
+    Play,
+    Back,
+}
+
+#[derive(Default)]
+struct CodeMenuHandler {}
+
+impl ClickHandler for CodeMenuHandler {
+    type Type = ClickStatus;
+
+    fn handle_clicks(btns: &[Button<'_>], x: i32, y: i32) -> Option<Self::Type> {
+        if btns[0].inside(x, y) {
+            Some(ClickStatus::Paste)
+        } else if btns[1].inside(x, y) {
+            Some(ClickStatus::Play)
+        } else if btns[2].inside(x, y) {
+            Some(ClickStatus::Back)
+        } else {
+            None
+        }
+    }
+}
+
+fn build<'a>(
+    tex_creator: &'a TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    font_res: &Resource<[u8]>,
+    code: &str,
+    error: &str,
+) -> Result<Menu<'a, CodeMenuHandler>, String> {
+    let btns = buttons![
+        { 3, tex_creator, ttf, font_res, 800, 600 }:
+        (40, 440, 60, 6) : "Paste",
+        (310, 440, 60, 6) : "Play",
+        (580, 440, 60, 6) : "Back"
+    ];
+    let mut texts = texts![
+        { tex_creator, ttf, font_res, 800, 600 }:
+        (POS_CENTERED, 40, 50) : "Play Code",
+        (POS_CENTERED, 130, 20) : "Type or paste the code of a board",
+        (POS_CENTERED, 380, 20) : error
+    ];
+    // Text can't be empty, so the cursor is always shown. Only characters of
+    // the code alphabet are typed in, so the lines split anywhere.
+    let code = format!("{code}_");
+    let lines: Vec<&[u8]> = code.as_bytes().chunks(LINE).collect();
+    let shown = &lines[lines.len().saturating_sub(LINES)..];
+    for (line, y) in shown.iter().zip((180..).step_by(30)) {
+        texts.extend(texts![
+            { tex_creator, ttf, font_res, 800, 600 }:
+            (POS_CENTERED, y, 24) : std::str::from_utf8(line).unwrap()
+        ]);
+    }
+    Ok(Menu::new(btns, texts, (800, 600)))
+}
+
+/// Asks for a board code and decodes it into `layout`. Returns
+/// `ClickStatus::Play` once that worked, or `None` if the window was closed.
+pub fn code_menu(
+    tex_creator: &TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    event_pump: &mut EventPump,
+    canvas: &mut WindowCanvas,
+    font_res: &Resource<[u8]>,
+    audio: &Audio,
+    layout: &mut Option<Layout>,
+) -> Result<Option<ClickStatus>, String> {
+    let font = ttf.load_font_from_rwops(RWops::from_bytes(font_res)?, 40)?;
+    let video = canvas.window().subsystem().clone();
+    let mut code = String::new();
+    let mut error = " ".to_string();
+    let mut code_menu = build(tex_creator, ttf, font_res, &code, &error)?;
+    video.text_input().start();
+
+    let status = 'top: loop {
+        let mut changed = false;
+        let mut status = None;
+        for e in event_pump.poll_iter() {
+            match e {
+                Event::Quit { .. } => break 'top None,
+                Event::TextInput { text, .. } => {
+                    code.extend(text.chars().filter(|&c| Layout::is_code_char(c)));
+                    changed = true;
+                }
+                Event::KeyDown {
+                    keycode: Some(key),
+                    keymod,
+                    ..
+                } => {
+                    status = match key {
+                        Keycode::Backspace => {
+                            code.pop();
+                            changed = true;
+                            None
+                        }
+                        Keycode::V if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD) => {
+                            Some(ClickStatus::Paste)
+                        }
+                        Keycode::Return | Keycode::KpEnter => Some(ClickStatus::Play),
+                        Keycode::Escape => Some(ClickStatus::Back),
+                        _ => status,
+                    };
+                }
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => status = code_menu.handle_clicks(x, y, audio).or(status),
+                _ => (),
+            }
+        }
+
+        match status {
+            Some(ClickStatus::Paste) => {
+                match video.clipboard().clipboard_text() {
+                    Ok(text) => code.extend(text.chars().filter(|&c| Layout::is_code_char(c))),
+                    Err(e) => error = format!("Could not paste: {e}"),
+                }
+                changed = true;
+            }
+            Some(ClickStatus::Play) => match Layout::from_code(&code) {
+                Ok(decoded) => {
+                    *layout = Some(decoded);
+                    break Some(ClickStatus::Play);
+                }
+                Err(e) => {
+                    error = e;
+                    changed = true;
+                }
+            },
+            Some(ClickStatus::Back) => break Some(ClickStatus::Back),
+            None => (),
+        }
+        if changed {
+            code_menu = build(tex_creator, ttf, font_res, &code, &error)?;
+        }
+
+        canvas.clear();
+        code_menu.render(canvas, &font, tex_creator)?;
+        std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
+    };
+    video.text_input().stop();
+    Ok(status)
+}
@@ -9,14 +9,15 @@ use sdl2::{
     rect::Rect,
     render::{TextureCreator, WindowCanvas},
     rwops::RWops,
-    ttf::Sdl2TtfContext,
+    ttf::{Font, Sdl2TtfContext},
     video::WindowContext,
     EventPump,
 };
 
 use crate::{
+    audio::Audio,
     buttons,
-    game::{map::Map, tile::TILE_SIZE, Stage},
+    game::{layout::Layout, stats::Stats, Stage},
     texts,
 };
 
@@ -27,10 +28,63 @@ use super::{
     POS_CENTERED,
 };
 
+/// A finished game, shown next to the end menu.
+pub trait Playfield<'a> {
+    fn width(&self) -> i32;
+
+    fn height(&self) -> i32;
+
+    fn render(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        font: &Font,
+        tex_creator: &'a TextureCreator<WindowContext>,
+        status: Option<bool>,
+    ) -> Result<(), String>;
+
+    /// The line shown under the result.
+    fn summary(&self) -> String;
+
+    /// What the "Play ... Again" button offers.
+    fn name(&self) -> String;
+
+    /// The statistics of a single board, for games played on one.
+    fn stats(&self) -> Option<Stats> {
+        None
+    }
+
+    /// How the mines hit in a practice game are judged.
+    fn analysis(&self) -> Option<String> {
+        None
+    }
+
+    /// Where the mines were, for games played on a single board.
+    fn layout(&self) -> Option<Layout> {
+        None
+    }
+
+    /// A summary of the game to share with others, for games that have one.
+    fn share(&self) -> Option<String> {
+        None
+    }
+}
+
 pub enum ClickStatus {
     Menu,
     Exit,
     PlayAgain,
+    Export,
+    CopyCode,
+    Share,
+}
+
+/// What became of the buttons that don't leave the end menu.
+#[derive(Clone, Copy, Default)]
+struct Feedback {
+    // Whether the board was exported or copied, once it was tried.
+    exported: Option<bool>,
+    copied: Option<bool>,
+    shared: Option<bool>,
 }
 
 #[derive(Default)]
@@ -46,12 +100,113 @@ impl ClickHandler for EndMenuHandler {
             Some(ClickStatus::Exit)
         } else if btns[2].inside(x, y) {
             Some(ClickStatus::PlayAgain)
+        } else if btns.get(3).is_some_and(|e| e.inside(x, y)) {
+            Some(ClickStatus::Export)
+        } else if btns.get(4).is_some_and(|e| e.inside(x, y)) {
+            Some(ClickStatus::CopyCode)
+        } else if btns.get(5).is_some_and(|e| e.inside(x, y)) {
+            Some(ClickStatus::Share)
         } else {
             None
         }
     }
 }
 
+fn build<'a>(
+    state: &Stage,
+    tex_creator: &'a TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    font_res: &Resource<[u8]>,
+    map: &dyn Playfield<'a>,
+    feedback: Feedback,
+) -> Result<Menu<'a, EndMenuHandler>, String> {
+    let map_width = map.width();
+    let mut btns = buttons![
+        { 5, tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+        (POS_CENTERED, 300, 64, 7) : "Menu",
+        (POS_CENTERED, 400, 64, 7) : "Exit",
+        (POS_CENTERED, 500, 64, 5) : &format!("Play {} Again", map.name())
+    ];
+    // Only games on a single board can be shared, so the share button always
+    // comes after the board's.
+    let layout = map.layout();
+    let mut labels = vec![];
+    if layout.is_some() {
+        labels.push(match feedback.exported {
+            None => "Export",
+            Some(true) => "Saved!",
+            Some(false) => "Failed",
+        });
+        labels.push(match feedback.copied {
+            None => "Copy code",
+            Some(true) => "Copied!",
+            Some(false) => "Failed",
+        });
+    }
+    if map.share().is_some() {
+        labels.push(match feedback.shared {
+            None => "Share",
+            Some(true) => "Copied!",
+            Some(false) => "Failed",
+        });
+    }
+    let row = 130 * i32::try_from(labels.len()).unwrap() - 10;
+    let left = (400 - row) / 2;
+    for (label, i) in labels.into_iter().zip(0..) {
+        btns.extend(buttons![
+            { 2, tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+            (left + 130 * i, 250, 60, 9) : label
+        ]);
+    }
+    let mut texts = texts![
+        { tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+        (POS_CENTERED, 50, 50) : match state {
+            Stage::Lose => "You Lose!",
+            Stage::TimeOut => "Time's Up!",
+            Stage::Playing | Stage::Win => "You Win!",
+        },
+        (5, 670, 20) : &format!("minesweeper v{}", env!("CARGO_PKG_VERSION"))
+    ];
+    // The code goes under the buttons, broken up to fit. Codes of big boards
+    // can only be copied.
+    if let Some(layout) = layout {
+        let code = layout.code();
+        let lines: Vec<&[u8]> = if code.len() <= CODE_LINE * CODE_LINES {
+            code.as_bytes().chunks(CODE_LINE).collect()
+        } else {
+            vec![b"The code is too long to show, copy it instead"]
+        };
+        for (line, y) in lines.into_iter().zip((590..).step_by(16)) {
+            texts.extend(texts![
+                { tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+                (POS_CENTERED, y, 14) : std::str::from_utf8(line).unwrap()
+            ]);
+        }
+    }
+    // The summary, analysis and statistics go one under the other, between
+    // the title and the buttons.
+    let lines = std::iter::once(map.summary())
+        .chain(map.analysis())
+        .chain(map.stats().as_ref().map_or_else(Vec::new, Stats::lines));
+    for (line, y) in lines.zip((100..).step_by(24)) {
+        texts.extend(texts![
+            { tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+            (POS_CENTERED, y, 20) : &line
+        ]);
+    }
+    Ok(Menu::new(
+        btns,
+        texts,
+        (400 + u32::try_from(map_width).unwrap(), 700),
+    ))
+}
+
+/// How many characters of a board code fit on one line.
+const CODE_LINE: usize = 50;
+/// How many lines of the code fit above the version.
+const CODE_LINES: usize = 5;
+
+#[allow(clippy::too_many_arguments)]
 pub fn end_menu<'a>(
     state: &Stage,
     tex_creator: &'a TextureCreator<WindowContext>,
@@ -59,26 +214,14 @@ pub fn end_menu<'a>(
     event_pump: &mut EventPump,
     canvas: &mut WindowCanvas,
     font_res: &Resource<[u8]>,
-    map: &mut Map<'a>,
+    audio: &Audio,
+    map: &mut dyn Playfield<'a>,
 ) -> Result<Option<ClickStatus>, String> {
     let font = ttf.load_font_from_rwops(RWops::from_bytes(font_res)?, 40)?;
-    let map_width = map.dim.1 * TILE_SIZE;
-    let map_height = map.dim.0 * TILE_SIZE;
-    let mut end_menu = Menu::<EndMenuHandler>::new(
-        buttons![
-            { 5, tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
-            (POS_CENTERED, 300, 64, 7) : "Menu",
-            (POS_CENTERED, 400, 64, 7) : "Exit",
-            (POS_CENTERED, 500, 64, 5) : &format!("Play {} Again", map.size.to_string())
-        ],
-        texts![
-            { tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
-            (POS_CENTERED, 50, 50) : if let Stage::Lose = state {"You Lose!"} else {"You Win!"},
-            (POS_CENTERED, 200, 20) : &format!("Time: {} | Progress: {}%", map.stopwatch.elapsed().as_secs(), map.percentage),
-            (5, 610, 20) : &format!("minesweeper v{}", env!("CARGO_PKG_VERSION"))
-        ],
-        (400 + u32::try_from(map_width).unwrap(), 650),
-    );
+    let map_width = map.width();
+    let map_height = map.height();
+    let mut feedback = Feedback::default();
+    let mut end_menu = build(state, tex_creator, ttf, font_res, map, feedback)?;
 
     loop {
         for e in event_pump.poll_iter() {
@@ -90,9 +233,26 @@ pub fn end_menu<'a>(
                     y,
                     ..
                 } => {
-                    if let Some(status) = end_menu.handle_clicks(x, y) {
-                        return Ok(Some(status));
+                    let clipboard = canvas.window().subsystem().clipboard();
+                    match end_menu.handle_clicks(x, y, audio) {
+                        // Copies the summary so it can be pasted anywhere.
+                        Some(ClickStatus::Share) => {
+                            let share = map.share().unwrap();
+                            feedback.shared = Some(clipboard.set_clipboard_text(&share).is_ok());
+                        }
+                        Some(ClickStatus::CopyCode) => {
+                            let code = map.layout().unwrap().code();
+                            feedback.copied = Some(clipboard.set_clipboard_text(&code).is_ok());
+                        }
+                        // Saves the board to the boards directory, where it
+                        // can be loaded from again.
+                        Some(ClickStatus::Export) => {
+                            feedback.exported = Some(map.layout().unwrap().save().is_ok());
+                        }
+                        Some(status) => return Ok(Some(status)),
+                        None => continue,
                     }
+                    end_menu = build(state, tex_creator, ttf, font_res, map, feedback)?;
                 }
                 _ => (),
             }
@@ -13,19 +13,214 @@ use sdl2::{
 use std::time::Duration;
 
 use crate::{
-    buttons, texts,
+    audio::Audio,
+    buttons,
+    date::Date,
+    game::{
+        endless::Endless,
+        layout::Layout,
+        mask::Mask,
+        puzzle::Puzzle,
+        replay::Replay,
+        rules::{Neighborhood, Rules},
+    },
+    scores::Scores,
+    texts,
     ui::{button::Button, text::Text, POS_CENTERED},
 };
 
-use super::menu::{ClickHandler, Menu};
+use super::{
+    code_menu::{self, code_menu},
+    menu::{ClickHandler, Menu},
+    puzzle_menu::{self, puzzle_menu},
+    stats_menu::stats_menu,
+};
 
+#[derive(Clone, Copy)]
 pub enum ClickStatus {
     Small,
     Normal,
     Large,
+    FirstClick,
+    Topology,
+    Wrap,
+    Neighborhood,
+    Shape,
+    MultiMine,
+    Endless,
+    Daily,
+    Lives,
+    Clock,
+    TimeAttack,
+    Practice,
+    Stats,
+    PlayCode,
+    Puzzles,
+    Volume,
+    Sound,
     // Custom,
 }
 
+/// Everything picked in the main menu besides the game to play. Kept between
+/// games so the menu comes back the way it was left.
+pub struct Settings {
+    pub rules: Rules,
+    pub mask: Option<Mask>,
+    /// Boards are played against their size's time limit.
+    pub countdown: bool,
+    /// Mines don't end the game, and every one hit is judged as a forced
+    /// guess or a mistake. Practice games are never ranked.
+    pub practice: bool,
+    /// A board given on the command line or as a code, which is played
+    /// instead of one from the menu until the menu is opened again.
+    pub layout: Option<Layout>,
+    /// A replay given on the command line, which is watched instead.
+    pub replay: Option<Replay>,
+    /// The puzzle picked from the puzzle list.
+    pub puzzle: Option<Puzzle>,
+    /// How loud sound effects are, in percent.
+    pub volume: u8,
+    /// Turns sound off without losing the volume.
+    pub muted: bool,
+    /// The mask files that could not be read the last time the shapes were
+    /// looked up.
+    pub skipped_masks: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            rules: Rules::default(),
+            mask: None,
+            countdown: false,
+            practice: false,
+            layout: None,
+            replay: None,
+            puzzle: None,
+            volume: 100,
+            muted: false,
+            skipped_masks: vec![],
+        }
+    }
+}
+
+impl Settings {
+    /// The volumes the volume toggle goes through.
+    pub const VOLUMES: [u8; 4] = [25, 50, 75, 100];
+    /// The buttons that change a setting, in the order the pause menu lists
+    /// them.
+    pub const TOGGLES: [ClickStatus; 11] = [
+        ClickStatus::FirstClick,
+        ClickStatus::Topology,
+        ClickStatus::Wrap,
+        ClickStatus::Neighborhood,
+        ClickStatus::Shape,
+        ClickStatus::MultiMine,
+        ClickStatus::Lives,
+        ClickStatus::Clock,
+        ClickStatus::Practice,
+        ClickStatus::Volume,
+        ClickStatus::Sound,
+    ];
+
+    /// How loud sound effects are played, in percent.
+    pub fn loudness(&self) -> u8 {
+        if self.muted {
+            0
+        } else {
+            self.volume
+        }
+    }
+
+    /// The text of a toggle, showing what it is set to.
+    pub fn label(&self, toggle: ClickStatus) -> String {
+        let rules = self.rules;
+        match toggle {
+            ClickStatus::FirstClick => format!("First click: {}", rules.first_click),
+            ClickStatus::Topology => format!("Board: {}", rules.topology),
+            ClickStatus::Wrap if rules.wrap => "Edges: Wrap".to_string(),
+            ClickStatus::Wrap => "Edges: Walls".to_string(),
+            ClickStatus::Neighborhood => format!("Numbers: {}", rules.neighborhood),
+            ClickStatus::Shape => format!(
+                "Shape: {}",
+                self.mask.as_ref().map_or("Rectangle", |e| &e.name)
+            ),
+            ClickStatus::MultiMine if rules.multi_mine => "Mines: Up to 3".to_string(),
+            ClickStatus::MultiMine => "Mines: Single".to_string(),
+            ClickStatus::Lives => format!("Lives: {}", rules.lives),
+            ClickStatus::Clock if self.countdown => "Clock: Countdown".to_string(),
+            ClickStatus::Clock => "Clock: Up".to_string(),
+            ClickStatus::Practice if self.practice => "Practice: On".to_string(),
+            ClickStatus::Practice => "Practice: Off".to_string(),
+            ClickStatus::Volume => format!("Volume: {}%", self.volume),
+            ClickStatus::Sound if self.muted => "Sound: Off".to_string(),
+            ClickStatus::Sound => "Sound: On".to_string(),
+            ClickStatus::Small
+            | ClickStatus::Normal
+            | ClickStatus::Large
+            | ClickStatus::Endless
+            | ClickStatus::Daily
+            | ClickStatus::TimeAttack
+            | ClickStatus::Stats
+            | ClickStatus::PlayCode
+            | ClickStatus::Puzzles => unreachable!(),
+        }
+    }
+
+    /// Applies a click on one of the toggles, or returns `false` if `status`
+    /// is not a toggle.
+    pub fn toggle(&mut self, status: ClickStatus) -> bool {
+        let rules = &mut self.rules;
+        match status {
+            ClickStatus::FirstClick => rules.first_click = rules.first_click.next(),
+            ClickStatus::Topology => {
+                rules.topology = rules.topology.next();
+                if !rules.neighborhood.supports(rules.topology) {
+                    rules.neighborhood = Neighborhood::Standard;
+                }
+            }
+            ClickStatus::Wrap => rules.wrap = !rules.wrap,
+            ClickStatus::Neighborhood => {
+                rules.neighborhood = rules.neighborhood.next(rules.topology);
+            }
+            ClickStatus::MultiMine => rules.multi_mine = !rules.multi_mine,
+            ClickStatus::Lives => {
+                let i = Rules::LIVES.iter().position(|&e| e == rules.lives);
+                rules.lives = Rules::LIVES[i.map_or(0, |i| (i + 1) % Rules::LIVES.len())];
+            }
+            ClickStatus::Clock => self.countdown = !self.countdown,
+            ClickStatus::Practice => self.practice = !self.practice,
+            ClickStatus::Volume => {
+                let i = Settings::VOLUMES.iter().position(|&e| e == self.volume);
+                self.volume = Settings::VOLUMES[i.map_or(0, |i| (i + 1) % Settings::VOLUMES.len())];
+            }
+            ClickStatus::Sound => self.muted = !self.muted,
+            ClickStatus::Shape => {
+                // Cycles through the masks and back to the plain rectangle.
+                let (mut masks, skipped) = Mask::available();
+                self.skipped_masks = skipped;
+                let next = self.mask.as_ref().map_or(0, |current| {
+                    masks
+                        .iter()
+                        .position(|e| e.name == current.name)
+                        .map_or(masks.len(), |i| i + 1)
+                });
+                self.mask = (next < masks.len()).then(|| masks.swap_remove(next));
+            }
+            ClickStatus::Small
+            | ClickStatus::Normal
+            | ClickStatus::Large
+            | ClickStatus::Endless
+            | ClickStatus::Daily
+            | ClickStatus::TimeAttack
+            | ClickStatus::Stats
+            | ClickStatus::PlayCode
+            | ClickStatus::Puzzles => return false,
+        }
+        true
+    }
+}
+
 #[derive(Default)]
 struct MainMenuHandler {}
 
@@ -39,7 +234,41 @@ impl ClickHandler for MainMenuHandler {
             Some(ClickStatus::Normal)
         } else if btns[2].inside(x, y) {
             Some(ClickStatus::Large)
-        // } else if btns[3].inside(m) {
+        } else if btns[3].inside(x, y) {
+            Some(ClickStatus::FirstClick)
+        } else if btns[4].inside(x, y) {
+            Some(ClickStatus::Topology)
+        } else if btns[5].inside(x, y) {
+            Some(ClickStatus::Wrap)
+        } else if btns[6].inside(x, y) {
+            Some(ClickStatus::Neighborhood)
+        } else if btns[7].inside(x, y) {
+            Some(ClickStatus::Shape)
+        } else if btns[8].inside(x, y) {
+            Some(ClickStatus::MultiMine)
+        } else if btns[9].inside(x, y) {
+            Some(ClickStatus::Endless)
+        } else if btns[10].inside(x, y) {
+            Some(ClickStatus::Daily)
+        } else if btns[11].inside(x, y) {
+            Some(ClickStatus::Lives)
+        } else if btns[12].inside(x, y) {
+            Some(ClickStatus::Clock)
+        } else if btns[13].inside(x, y) {
+            Some(ClickStatus::TimeAttack)
+        } else if btns[14].inside(x, y) {
+            Some(ClickStatus::Practice)
+        } else if btns[15].inside(x, y) {
+            Some(ClickStatus::Stats)
+        } else if btns[16].inside(x, y) {
+            Some(ClickStatus::PlayCode)
+        } else if btns[17].inside(x, y) {
+            Some(ClickStatus::Puzzles)
+        } else if btns[18].inside(x, y) {
+            Some(ClickStatus::Volume)
+        } else if btns[19].inside(x, y) {
+            Some(ClickStatus::Sound)
+        // } else if btns[6].inside(m) {
         //     Some(MainMenuClickStatus::Custom)
         } else {
             None
@@ -47,32 +276,96 @@ impl ClickHandler for MainMenuHandler {
     }
 }
 
+fn build<'a>(
+    tex_creator: &'a TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    font_res: &Resource<[u8]>,
+    settings: &Settings,
+) -> Result<Menu<'a, MainMenuHandler>, String> {
+    let mut btns = buttons![
+        { 5, tex_creator, ttf, font_res, 800, 600 }:
+        (POS_CENTERED, 300, 64, 7) : "Small",
+        (POS_CENTERED, 400, 64, 7) : "Normal",
+        (POS_CENTERED, 500, 64, 7) : "Large"
+        // (POS_CENTERED, 600, 64) : "Custom"
+    ];
+    btns.extend(buttons![
+        { 2, tex_creator, ttf, font_res, 800, 600 }:
+        (20, 195, 120, 9) : &settings.label(ClickStatus::FirstClick),
+        (280, 195, 120, 9) : &settings.label(ClickStatus::Topology),
+        (540, 195, 120, 9) : &settings.label(ClickStatus::Wrap),
+        (20, 240, 120, 9) : &settings.label(ClickStatus::Neighborhood),
+        (280, 240, 120, 9) : &settings.label(ClickStatus::Shape),
+        (540, 240, 120, 9) : &settings.label(ClickStatus::MultiMine)
+    ]);
+    btns.extend(buttons![
+        { 3, tex_creator, ttf, font_res, 800, 600 }:
+        (600, 530, 60, 6) : if Endless::has_save() { "Continue Endless" } else { "Endless" },
+        // The daily board can still be played after the attempt, just not for the record.
+        (600, 470, 60, 6) : if Scores::load().daily(Date::today()).is_some() { "Daily (practice)" } else { "Daily" }
+    ]);
+    btns.extend(buttons![
+        { 2, tex_creator, ttf, font_res, 800, 600 }:
+        (20, 470, 100, 9) : &settings.label(ClickStatus::Lives),
+        (20, 510, 100, 9) : &settings.label(ClickStatus::Clock)
+    ]);
+    btns.extend(buttons![
+        { 3, tex_creator, ttf, font_res, 800, 600 }:
+        (600, 410, 60, 6) : "Time Attack"
+    ]);
+    btns.extend(buttons![
+        { 2, tex_creator, ttf, font_res, 800, 600 }:
+        (20, 350, 100, 9) : &settings.label(ClickStatus::Practice)
+    ]);
+    btns.extend(buttons![
+        { 3, tex_creator, ttf, font_res, 800, 600 }:
+        (600, 350, 60, 6) : "Stats",
+        (600, 290, 60, 6) : "Play Code",
+        (20, 290, 60, 6) : "Puzzles"
+    ]);
+    btns.extend(buttons![
+        { 2, tex_creator, ttf, font_res, 800, 600 }:
+        (20, 390, 100, 9) : &settings.label(ClickStatus::Volume),
+        (20, 430, 100, 9) : &settings.label(ClickStatus::Sound)
+    ]);
+    let mut texts = texts![
+        { tex_creator, ttf, font_res, 800, 600 }:
+        (POS_CENTERED, 50, 90) : "MINESWEEPER",
+        (5, 560, 20) : &format!("minesweeper v{}", env!("CARGO_PKG_VERSION"))
+    ];
+    // Shapes that failed to load are left out, so their authors can see why.
+    if let Some(first) = settings.skipped_masks.first() {
+        let more = match settings.skipped_masks.len() {
+            1 => String::new(),
+            n => format!(" (and {} more)", n - 1),
+        };
+        texts.extend(texts![
+            { tex_creator, ttf, font_res, 800, 600 }:
+            (POS_CENTERED, 160, 16) : &format!("Skipped {first}{more}")
+        ]);
+    }
+    Ok(Menu::new(btns, texts, (800, 600)))
+}
+
 pub fn main_menu(
     tex_creator: &TextureCreator<WindowContext>,
     ttf: &Sdl2TtfContext,
     event_pump: &mut EventPump,
     canvas: &mut WindowCanvas,
     font_res: &Resource<[u8]>,
+    audio: &Audio,
+    settings: &mut Settings,
 ) -> Result<Option<ClickStatus>, String> {
     let res = resource!("res/font/opensans.ttf");
     let font = ttf.load_font_from_rwops(RWops::from_bytes(&res)?, 50)?;
-    let mut main_menu = Menu::<MainMenuHandler>::new(
-        buttons![
-            { 5, tex_creator, ttf, font_res, 800, 600 }:
-            (POS_CENTERED, 300, 64, 7) : "Small",
-            (POS_CENTERED, 400, 64, 7) : "Normal",
-            (POS_CENTERED, 500, 64, 7) : "Large"
-            // (POS_CENTERED, 600, 64) : "Custom"
-        ],
-        texts![
-            { tex_creator, ttf, font_res, 800, 600 }:
-            (POS_CENTERED, 50, 90) : "MINESWEEPER",
-            (5, 560, 20) : &format!("minesweeper v{}", env!("CARGO_PKG_VERSION"))
-        ],
-        (800, 600),
-    );
+    let mut main_menu = build(tex_creator, ttf, font_res, settings)?;
 
     'top: loop {
+        // The stats, code and puzzle screens take over the events, so they
+        // are opened once this frame's events are handled.
+        let mut show_stats = false;
+        let mut show_code = false;
+        let mut show_puzzles = false;
         for e in event_pump.poll_iter() {
             match e {
                 Event::Quit { .. } => break 'top,
@@ -81,14 +374,55 @@ pub fn main_menu(
                     x,
                     y,
                     ..
-                } => {
-                    if let Some(status) = main_menu.handle_clicks(x, y) {
-                        return Ok(Some(status));
+                } => match main_menu.handle_clicks(x, y, audio) {
+                    Some(status) if settings.toggle(status) => {
+                        audio.set_volume(settings.loudness());
+                        main_menu = build(tex_creator, ttf, font_res, settings)?;
                     }
-                }
+                    Some(ClickStatus::Stats) => show_stats = true,
+                    Some(ClickStatus::PlayCode) => show_code = true,
+                    Some(ClickStatus::Puzzles) => show_puzzles = true,
+                    Some(status) => return Ok(Some(status)),
+                    None => (),
+                },
                 _ => (),
             }
         }
+        if show_stats && !stats_menu(tex_creator, ttf, event_pump, canvas, font_res, audio)? {
+            break;
+        }
+        if show_code {
+            let layout = &mut settings.layout;
+            match code_menu(
+                tex_creator,
+                ttf,
+                event_pump,
+                canvas,
+                font_res,
+                audio,
+                layout,
+            )? {
+                Some(code_menu::ClickStatus::Play) => return Ok(Some(ClickStatus::PlayCode)),
+                Some(_) => (),
+                None => break,
+            }
+        }
+        if show_puzzles {
+            let puzzle = &mut settings.puzzle;
+            match puzzle_menu(
+                tex_creator,
+                ttf,
+                event_pump,
+                canvas,
+                font_res,
+                audio,
+                puzzle,
+            )? {
+                Some(puzzle_menu::ClickStatus::Pick(_)) => return Ok(Some(ClickStatus::Puzzles)),
+                Some(_) => (),
+                None => break,
+            }
+        }
 
         canvas.set_draw_color(Color::RGB(28, 28, 28));
         canvas.clear();
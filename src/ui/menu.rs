@@ -4,6 +4,8 @@ use sdl2::{
     video::{WindowContext, WindowPos},
 };
 
+use crate::audio::{Audio, Sound};
+
 use super::{button::Button, text::Text};
 
 #[macro_export]
@@ -74,8 +76,13 @@ impl<'a, C: ClickHandler> Menu<'a, C> {
         }
     }
 
-    pub fn handle_clicks(&self, x: i32, y: i32) -> Option<C::Type> {
-        C::handle_clicks(&self.btns, x, y)
+    /// What the button at (x, y) does, with a click sound if there is one.
+    pub fn handle_clicks(&self, x: i32, y: i32, audio: &Audio) -> Option<C::Type> {
+        let status = C::handle_clicks(&self.btns, x, y);
+        if status.is_some() {
+            audio.play(Sound::Click);
+        }
+        status
     }
 
     pub fn render(
@@ -1,7 +1,11 @@
 pub mod button;
+pub mod code_menu;
 pub mod end_menu;
 pub mod main_menu;
 pub mod menu;
+pub mod pause_menu;
+pub mod puzzle_menu;
+pub mod stats_menu;
 pub mod text;
 
 pub const POS_CENTERED: i32 = -1;
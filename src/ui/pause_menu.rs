@@ -0,0 +1,220 @@
+use std::time::Duration;
+
+use resource::Resource;
+
+use sdl2::{
+    event::Event,
+    keyboard::Keycode,
+    mouse::MouseButton,
+    pixels::Color,
+    rect::Rect,
+    render::{TextureCreator, WindowCanvas},
+    rwops::RWops,
+    ttf::Sdl2TtfContext,
+    video::WindowContext,
+    EventPump,
+};
+
+use crate::{audio::Audio, buttons, texts};
+
+use super::{
+    button::Button,
+    main_menu::{self, Settings},
+    menu::{ClickHandler, Menu},
+    text::Text,
+    POS_CENTERED,
+};
+
+pub enum ClickStatus {
+    Resume,
+    Restart,
+    Settings,
+    Menu,
+}
+
+#[derive(Default)]
+struct PauseMenuHandler {}
+
+impl ClickHandler for PauseMenuHandler {
+    type Type = ClickStatus;
+
+    fn handle_clicks(btns: &[Button<'_>], x: i32, y: i32) -> Option<Self::Type> {
+        if btns[0].inside(x, y) {
+            Some(ClickStatus::Resume)
+        } else if btns[1].inside(x, y) {
+            Some(ClickStatus::Restart)
+        } else if btns[2].inside(x, y) {
+            Some(ClickStatus::Settings)
+        } else if btns[3].inside(x, y) {
+            Some(ClickStatus::Menu)
+        } else {
+            None
+        }
+    }
+}
+
+/// A toggle on the settings page, or `None` for the back button.
+#[derive(Default)]
+struct SettingsHandler {}
+
+impl ClickHandler for SettingsHandler {
+    type Type = Option<main_menu::ClickStatus>;
+
+    fn handle_clicks(btns: &[Button<'_>], x: i32, y: i32) -> Option<Self::Type> {
+        let i = btns.iter().position(|e| e.inside(x, y))?;
+        Some(Settings::TOGGLES.get(i).copied())
+    }
+}
+
+fn build<'a>(
+    tex_creator: &'a TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    font_res: &Resource<[u8]>,
+    map_width: i32,
+) -> Result<Menu<'a, PauseMenuHandler>, String> {
+    Ok(Menu::new(
+        buttons![
+            { 5, tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+            (POS_CENTERED, 150, 64, 7) : "Resume",
+            (POS_CENTERED, 250, 64, 7) : "Restart",
+            (POS_CENTERED, 350, 64, 7) : "Settings",
+            (POS_CENTERED, 450, 64, 7) : "Menu"
+        ],
+        texts![
+            { tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+            (POS_CENTERED, 50, 50) : "Paused"
+        ],
+        (400 + u32::try_from(map_width).unwrap(), 650),
+    ))
+}
+
+/// The same toggles as the main menu, in one column.
+fn build_settings<'a>(
+    tex_creator: &'a TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    font_res: &Resource<[u8]>,
+    map_width: i32,
+    settings: &Settings,
+) -> Result<Menu<'a, SettingsHandler>, String> {
+    let font = ttf.load_font_from_rwops(RWops::from_bytes(font_res)?, 9 * 2)?;
+    let mut btns: Vec<Button> = Settings::TOGGLES
+        .iter()
+        .zip(0..)
+        .map(|(&toggle, i)| {
+            Button::new(
+                POS_CENTERED,
+                90 + i * 35,
+                map_width,
+                0,
+                120,
+                2,
+                tex_creator,
+                &settings.label(toggle),
+                &font,
+                400,
+                500,
+            )
+        })
+        .collect();
+    btns.extend(buttons![
+        { 3, tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+        (POS_CENTERED, 540, 60, 6) : "Back"
+    ]);
+    Ok(Menu::new(
+        btns,
+        texts![
+            { tex_creator, ttf, font_res, map_width, 0, 400, 500 }:
+            (POS_CENTERED, 30, 50) : "Settings",
+            (POS_CENTERED, 480, 20) : "Changes apply from the next game"
+        ],
+        (400 + u32::try_from(map_width).unwrap(), 650),
+    ))
+}
+
+/// Shows the pause menu next to the board, which is covered up so the game
+/// can't be studied while the clock is stopped. Pressing P or Escape again
+/// resumes. Returns `None` if the window was closed.
+#[allow(clippy::too_many_arguments)]
+pub fn pause_menu(
+    tex_creator: &TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    event_pump: &mut EventPump,
+    canvas: &mut WindowCanvas,
+    font_res: &Resource<[u8]>,
+    audio: &Audio,
+    (map_width, map_height): (i32, i32),
+    settings: &mut Settings,
+) -> Result<Option<ClickStatus>, String> {
+    let font = ttf.load_font_from_rwops(RWops::from_bytes(font_res)?, 40)?;
+    let mut pause_menu = build(tex_creator, ttf, font_res, map_width)?;
+    // Set while the settings page is shown instead of the pause menu.
+    let mut settings_menu: Option<Menu<SettingsHandler>> = None;
+
+    loop {
+        for e in event_pump.poll_iter() {
+            match e {
+                Event::Quit { .. } => return Ok(None),
+                Event::KeyDown {
+                    keycode: Some(Keycode::P | Keycode::Escape),
+                    ..
+                } => return Ok(Some(ClickStatus::Resume)),
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => {
+                    if let Some(menu) = &settings_menu {
+                        match menu.handle_clicks(x, y, audio) {
+                            Some(Some(toggle)) => {
+                                settings.toggle(toggle);
+                                audio.set_volume(settings.loudness());
+                                settings_menu = Some(build_settings(
+                                    tex_creator,
+                                    ttf,
+                                    font_res,
+                                    map_width,
+                                    settings,
+                                )?);
+                            }
+                            Some(None) => settings_menu = None,
+                            None => (),
+                        }
+                        continue;
+                    }
+                    match pause_menu.handle_clicks(x, y, audio) {
+                        Some(ClickStatus::Settings) => {
+                            settings_menu = Some(build_settings(
+                                tex_creator,
+                                ttf,
+                                font_res,
+                                map_width,
+                                settings,
+                            )?);
+                        }
+                        Some(status) => return Ok(Some(status)),
+                        None => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        canvas.clear();
+
+        canvas.set_draw_color(Color::RGB(100, 100, 100));
+        canvas.fill_rect(Rect::new(
+            0,
+            0,
+            u32::try_from(map_width + 5).unwrap(),
+            u32::try_from(map_height + 5).unwrap(),
+        ))?;
+        canvas.set_draw_color(Color::RGB(28, 28, 28));
+
+        match &mut settings_menu {
+            Some(menu) => menu.render(canvas, &font, tex_creator)?,
+            None => pause_menu.render(canvas, &font, tex_creator)?,
+        }
+        std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
+    }
+}
@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use resource::Resource;
+
+use sdl2::{
+    event::Event,
+    keyboard::Keycode,
+    mouse::MouseButton,
+    render::{TextureCreator, WindowCanvas},
+    rwops::RWops,
+    ttf::Sdl2TtfContext,
+    video::WindowContext,
+    EventPump,
+};
+
+use crate::{
+    audio::Audio,
+    buttons,
+    game::puzzle::{Pack, Progress, Puzzle},
+    texts,
+};
+
+use super::{
+    button::Button,
+    menu::{ClickHandler, Menu},
+    text::Text,
+    POS_CENTERED,
+};
+
+/// How many puzzles are listed on one page.
+const PAGE: usize = 8;
+
+pub enum ClickStatus {
+    Prev,
+    Next,
+    Back,
+    /// The puzzle at that place on the page.
+    Pick(usize),
+}
+
+#[derive(Default)]
+struct PuzzleMenuHandler {}
+
+impl ClickHandler for PuzzleMenuHandler {
+    type Type = ClickStatus;
+
+    fn handle_clicks(btns: &[Button<'_>], x: i32, y: i32) -> Option<Self::Type> {
+        let i = btns.iter().position(|e| e.inside(x, y))?;
+        Some(match i {
+            0 => ClickStatus::Prev,
+            1 => ClickStatus::Next,
+            2 => ClickStatus::Back,
+            _ => ClickStatus::Pick(i - 3),
+        })
+    }
+}
+
+/// A page of puzzles: the pack and the puzzles from `start` on.
+#[derive(Clone, Copy)]
+struct Page {
+    pack: usize,
+    start: usize,
+}
+
+fn pages(packs: &[Pack]) -> Vec<Page> {
+    packs
+        .iter()
+        .enumerate()
+        .flat_map(|(pack, e)| {
+            (0..e.puzzles.len())
+                .step_by(PAGE)
+                .map(move |start| Page { pack, start })
+        })
+        .collect()
+}
+
+fn build<'a>(
+    tex_creator: &'a TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    font_res: &Resource<[u8]>,
+    pack: &Pack,
+    page: Page,
+    progress: &Progress,
+    skipped: &[String],
+) -> Result<Menu<'a, PuzzleMenuHandler>, String> {
+    let mut btns = buttons![
+        { 3, tex_creator, ttf, font_res, 800, 600 }:
+        (40, 500, 60, 6) : "Prev",
+        (310, 500, 60, 6) : "Next",
+        (580, 500, 60, 6) : "Back"
+    ];
+    let puzzles = pack.puzzles.iter().skip(page.start).take(PAGE);
+    for (puzzle, i) in puzzles.zip(0..) {
+        let label = if progress.is_solved(puzzle) {
+            format!("{} (solved)", puzzle.layout.name)
+        } else {
+            puzzle.layout.name.clone()
+        };
+        btns.extend(buttons![
+            { 2, tex_creator, ttf, font_res, 800, 600 }:
+            (60 + 360 * (i % 2), 170 + 70 * (i / 2), 160, 9) : &label
+        ]);
+    }
+    let solved = pack
+        .puzzles
+        .iter()
+        .filter(|e| progress.is_solved(e))
+        .count();
+    let mut texts = texts![
+        { tex_creator, ttf, font_res, 800, 600 }:
+        (POS_CENTERED, 20, 50) : "Puzzles",
+        (POS_CENTERED, 110, 24) : &format!("{} ({solved}/{} solved)", pack.name, pack.puzzles.len())
+    ];
+    // Puzzles that failed the check are left out, so pack authors can see why.
+    if let Some(first) = skipped.first() {
+        let more = match skipped.len() {
+            1 => String::new(),
+            n => format!(" (and {} more)", n - 1),
+        };
+        texts.extend(texts![
+            { tex_creator, ttf, font_res, 800, 600 }:
+            (POS_CENTERED, 570, 16) : &format!("Skipped {first}{more}")
+        ]);
+    }
+    Ok(Menu::new(btns, texts, (800, 600)))
+}
+
+/// Lists the puzzles of every pack and puts the one picked into `puzzle`.
+/// Returns `ClickStatus::Pick` once one was picked, or `None` if the window
+/// was closed.
+pub fn puzzle_menu(
+    tex_creator: &TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    event_pump: &mut EventPump,
+    canvas: &mut WindowCanvas,
+    font_res: &Resource<[u8]>,
+    audio: &Audio,
+    puzzle: &mut Option<Puzzle>,
+) -> Result<Option<ClickStatus>, String> {
+    let font = ttf.load_font_from_rwops(RWops::from_bytes(font_res)?, 40)?;
+    let (packs, skipped) = Pack::available();
+    let pages = pages(&packs);
+    let progress = Progress::load();
+    let mut page = 0;
+    let mut puzzle_menu = build(
+        tex_creator,
+        ttf,
+        font_res,
+        &packs[pages[page].pack],
+        pages[page],
+        &progress,
+        &skipped,
+    )?;
+
+    loop {
+        let mut status = None;
+        for e in event_pump.poll_iter() {
+            match e {
+                Event::Quit { .. } => return Ok(None),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => status = Some(ClickStatus::Back),
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => status = puzzle_menu.handle_clicks(x, y, audio).or(status),
+                _ => (),
+            }
+        }
+
+        let turn = match status {
+            Some(ClickStatus::Prev) => (page + pages.len() - 1) % pages.len(),
+            Some(ClickStatus::Next) => (page + 1) % pages.len(),
+            Some(ClickStatus::Back) => return Ok(Some(ClickStatus::Back)),
+            Some(ClickStatus::Pick(i)) => {
+                let Page { pack, start } = pages[page];
+                *puzzle = Some(packs[pack].puzzles[start + i].clone());
+                return Ok(Some(ClickStatus::Pick(i)));
+            }
+            None => page,
+        };
+        if turn != page {
+            page = turn;
+            let pack = &packs[pages[page].pack];
+            puzzle_menu = build(
+                tex_creator,
+                ttf,
+                font_res,
+                pack,
+                pages[page],
+                &progress,
+                &skipped,
+            )?;
+        }
+
+        canvas.clear();
+        puzzle_menu.render(canvas, &font, tex_creator)?;
+        std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
+    }
+}
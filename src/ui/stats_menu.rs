@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use resource::Resource;
+
+use sdl2::{
+    event::Event,
+    mouse::MouseButton,
+    pixels::Color,
+    rect::Rect,
+    render::{TextureCreator, WindowCanvas},
+    rwops::RWops,
+    ttf::Sdl2TtfContext,
+    video::WindowContext,
+    EventPump,
+};
+
+use crate::{
+    audio::Audio,
+    buttons,
+    game::stats::seconds,
+    history::{History, Summary, BUCKETS, EXPORT_PATH},
+    scores::DAILY,
+    texts,
+};
+
+use super::{
+    button::Button,
+    menu::{ClickHandler, Menu},
+    text::Text,
+    POS_CENTERED,
+};
+
+/// The difficulties with a page of their own, as stored in the history.
+const MODES: [&str; 4] = ["small", "normal", "large", DAILY];
+
+// Where the histogram of completion times is drawn.
+const CHART: (i32, i32, u32, u32) = (420, 200, 340, 220);
+
+pub enum ClickStatus {
+    Mode(usize),
+    Export,
+    Back,
+}
+
+#[derive(Default)]
+struct StatsMenuHandler {}
+
+impl ClickHandler for StatsMenuHandler {
+    type Type = ClickStatus;
+
+    fn handle_clicks(btns: &[Button<'_>], x: i32, y: i32) -> Option<Self::Type> {
+        let i = btns.iter().position(|e| e.inside(x, y))?;
+        Some(match i {
+            _ if i < MODES.len() => ClickStatus::Mode(i),
+            _ if i == MODES.len() => ClickStatus::Export,
+            _ => ClickStatus::Back,
+        })
+    }
+}
+
+fn time(time: Option<Duration>) -> String {
+    time.map_or("-".to_string(), |e| format!("{}s", seconds(e)))
+}
+
+fn build<'a>(
+    tex_creator: &'a TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    font_res: &Resource<[u8]>,
+    mode: &str,
+    summary: &Summary,
+    // Says how the last export went.
+    note: &str,
+) -> Result<Menu<'a, StatsMenuHandler>, String> {
+    let mut btns = buttons![
+        { 2, tex_creator, ttf, font_res, 800, 600 }:
+        (40, 110, 80, 9) : if mode == MODES[0] { "[Small]" } else { "Small" },
+        (230, 110, 80, 9) : if mode == MODES[1] { "[Normal]" } else { "Normal" },
+        (420, 110, 80, 9) : if mode == MODES[2] { "[Large]" } else { "Large" },
+        (610, 110, 80, 9) : if mode == MODES[3] { "[Daily]" } else { "Daily" }
+    ];
+    btns.extend(buttons![
+        { 3, tex_creator, ttf, font_res, 800, 600 }:
+        (130, 500, 60, 6) : "Export CSV",
+        (490, 500, 60, 6) : "Back"
+    ]);
+    let (x, y, w, h) = CHART;
+    let mut texts = texts![
+        { tex_creator, ttf, font_res, 800, 600 }:
+        (POS_CENTERED, 20, 50) : "Statistics",
+        (40, 180, 20) : &format!("Played: {}", summary.played),
+        (40, 215, 20) : &format!("Won: {} ({:.0}%)", summary.won, summary.win_rate() * 100.0),
+        (40, 250, 20) : &format!("Current streak: {}", summary.streak),
+        (40, 285, 20) : &format!("Best streak: {}", summary.best_streak),
+        (40, 320, 20) : &format!("Best time: {}", time(summary.best_time)),
+        (40, 355, 20) : &format!("Average time: {}", time(summary.mean_time)),
+        (x, y - 35, 20) : "Wins by time",
+        (x, y + i32::try_from(h).unwrap(), 16) : "0s",
+        (x + i32::try_from(w).unwrap() - 50, y + i32::try_from(h).unwrap(), 16) :
+            &format!("{}s", (summary.bucket * u32::try_from(BUCKETS).unwrap()).as_secs())
+    ];
+    texts.extend(texts![
+        { tex_creator, ttf, font_res, 800, 600 }:
+        (POS_CENTERED, 560, 20) : note
+    ]);
+    Ok(Menu::new(btns, texts, (800, 600)))
+}
+
+/// Draws one bar per bucket, scaled so the fullest one fills the chart.
+fn render_histogram(canvas: &mut WindowCanvas, summary: &Summary) -> Result<(), String> {
+    let (x, y, w, h) = CHART;
+    canvas.set_draw_color(Color::RGB(50, 50, 50));
+    canvas.fill_rect(Rect::new(x, y, w, h))?;
+    let most = summary.histogram.iter().copied().max().unwrap_or(0).max(1);
+    let bar = w / u32::try_from(BUCKETS).unwrap();
+    canvas.set_draw_color(Color::RGB(90, 160, 90));
+    for (&count, i) in summary.histogram.iter().zip(0..) {
+        let height = h * count / most;
+        if height > 0 {
+            canvas.fill_rect(Rect::new(
+                x + i32::try_from(bar * i).unwrap() + 2,
+                y + i32::try_from(h - height).unwrap(),
+                bar - 4,
+                height,
+            ))?;
+        }
+    }
+    canvas.set_draw_color(Color::RGB(28, 28, 28));
+    Ok(())
+}
+
+/// Shows the lifetime statistics of every difficulty, one page at a time.
+/// Returns `false` if the window was closed.
+pub fn stats_menu(
+    tex_creator: &TextureCreator<WindowContext>,
+    ttf: &Sdl2TtfContext,
+    event_pump: &mut EventPump,
+    canvas: &mut WindowCanvas,
+    font_res: &Resource<[u8]>,
+    audio: &Audio,
+) -> Result<bool, String> {
+    let font = ttf.load_font_from_rwops(RWops::from_bytes(font_res)?, 40)?;
+    let history = History::load();
+    let mut mode = MODES[0];
+    let mut summary = history.summary(mode);
+    let mut stats_menu = build(tex_creator, ttf, font_res, mode, &summary, " ")?;
+
+    loop {
+        for e in event_pump.poll_iter() {
+            match e {
+                Event::Quit { .. } => return Ok(false),
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => match stats_menu.handle_clicks(x, y, audio) {
+                    Some(ClickStatus::Mode(i)) => {
+                        mode = MODES[i];
+                        summary = history.summary(mode);
+                        stats_menu = build(tex_creator, ttf, font_res, mode, &summary, " ")?;
+                    }
+                    Some(ClickStatus::Export) => {
+                        let note = match history.export() {
+                            Ok(()) => format!("Saved to {EXPORT_PATH}"),
+                            Err(e) => format!("Could not export: {e}"),
+                        };
+                        stats_menu = build(tex_creator, ttf, font_res, mode, &summary, &note)?;
+                    }
+                    Some(ClickStatus::Back) => return Ok(true),
+                    None => (),
+                },
+                _ => (),
+            }
+        }
+
+        canvas.clear();
+        render_histogram(canvas, &summary)?;
+        stats_menu.render(canvas, &font, tex_creator)?;
+        std::thread::sleep(Duration::from_nanos(1_000_000_000u64 / 60));
+    }
+}